@@ -0,0 +1,78 @@
+pub mod headless;
+pub mod window;
+
+use tcod::colors::Color;
+use tcod::console::{BackgroundFlag, TextAlignment};
+use tcod::input::{Event, Key};
+
+use std::any::Any;
+
+/// A rectangle of cells on a surface, for text that wraps inside it.
+/// A height of 0 lets the text run as far down as it needs.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Area {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+/// Something the game can draw on: the screen itself or an off-screen buffer.
+/// Mirrors the subset of `tcod::console::Console` the game uses, but can also be
+/// implemented without a window.
+pub trait Surface {
+    fn width(&self) -> i32;
+    fn height(&self) -> i32;
+    fn clear(&mut self);
+    fn set_default_foreground(&mut self, color: Color);
+    fn set_default_background(&mut self, color: Color);
+    fn put_char(&mut self, x: i32, y: i32, glyph: char, flag: BackgroundFlag);
+    fn set_char_background(&mut self, x: i32, y: i32, color: Color, flag: BackgroundFlag);
+    fn rect(&mut self, x: i32, y: i32, width: i32, height: i32, clear: bool, flag: BackgroundFlag);
+    fn print_ex(
+        &mut self,
+        x: i32,
+        y: i32,
+        flag: BackgroundFlag,
+        alignment: TextAlignment,
+        text: &str,
+    );
+    fn print_rect(&mut self, x: i32, y: i32, width: i32, height: i32, text: &str);
+    fn print_rect_ex(
+        &mut self,
+        area: Area,
+        flag: BackgroundFlag,
+        alignment: TextAlignment,
+        text: &str,
+    );
+    fn get_height_rect(&self, x: i32, y: i32, width: i32, height: i32, text: &str) -> i32;
+
+    /// lets a backend get back the concrete surface type it handed out
+    fn as_any(&self) -> &dyn Any;
+}
+
+/// Everything that needs a window: presenting surfaces and reading input.
+pub trait Backend {
+    /// create an off-screen surface that can later be blitted on the root
+    fn offscreen(&self, width: i32, height: i32) -> Box<dyn Surface>;
+    fn root(&mut self) -> &mut dyn Surface;
+    fn blit(
+        &mut self,
+        source: &dyn Surface,
+        source_pos: (i32, i32),
+        size: (i32, i32),
+        destination_pos: (i32, i32),
+        foreground_alpha: f32,
+        background_alpha: f32,
+    );
+    fn draw_menu_background(&mut self);
+    fn flush(&mut self);
+    /// cap how many times per second `flush` presents a frame, 0 for no limit
+    fn set_fps(&mut self, fps: i32);
+
+    fn window_closed(&self) -> bool;
+    fn is_fullscreen(&self) -> bool;
+    fn set_fullscreen(&mut self, fullscreen: bool);
+    fn check_for_event(&mut self) -> Option<Event>;
+    fn wait_for_keypress(&mut self) -> Key;
+}
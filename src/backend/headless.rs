@@ -0,0 +1,379 @@
+use crate::backend::{Area, Backend, Surface};
+
+use tcod::colors::{self, Color};
+use tcod::console::{BackgroundFlag, TextAlignment};
+use tcod::input::{Event, Key};
+
+use std::any::Any;
+use std::collections::VecDeque;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Cell {
+    pub glyph: char,
+    pub foreground: Color,
+    pub background: Color,
+}
+
+/// A grid of cells kept in memory, so rendering can be inspected without a window.
+pub struct MemoryConsole {
+    width: i32,
+    height: i32,
+    cells: Vec<Cell>,
+    default_foreground: Color,
+    default_background: Color,
+}
+
+impl MemoryConsole {
+    pub fn new(width: i32, height: i32) -> Self {
+        let blank = Cell {
+            glyph: ' ',
+            foreground: colors::WHITE,
+            background: colors::BLACK,
+        };
+        MemoryConsole {
+            width,
+            height,
+            cells: vec![blank; (width * height) as usize],
+            default_foreground: colors::WHITE,
+            default_background: colors::BLACK,
+        }
+    }
+
+    pub fn cell(&self, x: i32, y: i32) -> Option<&Cell> {
+        if self.in_bounds(x, y) {
+            Some(&self.cells[(y * self.width + x) as usize])
+        } else {
+            None
+        }
+    }
+
+    /// the glyphs of one row, handy for checking what text ended up on screen
+    pub fn line(&self, y: i32) -> String {
+        (0..self.width)
+            .filter_map(|x| self.cell(x, y))
+            .map(|cell| cell.glyph)
+            .collect()
+    }
+
+    fn in_bounds(&self, x: i32, y: i32) -> bool {
+        x >= 0 && x < self.width && y >= 0 && y < self.height
+    }
+
+    fn cell_mut(&mut self, x: i32, y: i32) -> Option<&mut Cell> {
+        if self.in_bounds(x, y) {
+            Some(&mut self.cells[(y * self.width + x) as usize])
+        } else {
+            None
+        }
+    }
+
+    fn print_line(
+        &mut self,
+        x: i32,
+        y: i32,
+        flag: BackgroundFlag,
+        alignment: TextAlignment,
+        text: &str,
+    ) {
+        let len = text.chars().count() as i32;
+        let start = match alignment {
+            TextAlignment::Left => x,
+            TextAlignment::Center => x - len / 2,
+            TextAlignment::Right => x - len + 1,
+        };
+        for (i, glyph) in text.chars().enumerate() {
+            self.put_char(start + i as i32, y, glyph, flag);
+        }
+    }
+}
+
+/// Split text into lines the way libtcod does: on explicit newlines and then
+/// greedily on spaces so no line is wider than `width` (0 means unlimited).
+fn wrap(text: &str, width: i32) -> Vec<String> {
+    let mut lines = vec![];
+    for paragraph in text.split('\n') {
+        let mut line = String::new();
+        for word in paragraph.split(' ') {
+            let fits = width <= 0
+                || line.is_empty()
+                || (line.chars().count() + 1 + word.chars().count()) as i32 <= width;
+            if !fits {
+                lines.push(line);
+                line = String::new();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(word);
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+impl Surface for MemoryConsole {
+    fn width(&self) -> i32 {
+        self.width
+    }
+
+    fn height(&self) -> i32 {
+        self.height
+    }
+
+    fn clear(&mut self) {
+        let blank = Cell {
+            glyph: ' ',
+            foreground: self.default_foreground,
+            background: self.default_background,
+        };
+        for cell in self.cells.iter_mut() {
+            *cell = blank;
+        }
+    }
+
+    fn set_default_foreground(&mut self, color: Color) {
+        self.default_foreground = color;
+    }
+
+    fn set_default_background(&mut self, color: Color) {
+        self.default_background = color;
+    }
+
+    fn put_char(&mut self, x: i32, y: i32, glyph: char, flag: BackgroundFlag) {
+        let (foreground, background) = (self.default_foreground, self.default_background);
+        if let Some(cell) = self.cell_mut(x, y) {
+            cell.glyph = glyph;
+            cell.foreground = foreground;
+            if flag != BackgroundFlag::None {
+                cell.background = background;
+            }
+        }
+    }
+
+    fn set_char_background(&mut self, x: i32, y: i32, color: Color, flag: BackgroundFlag) {
+        if let Some(cell) = self.cell_mut(x, y) {
+            if flag != BackgroundFlag::None {
+                cell.background = color;
+            }
+        }
+    }
+
+    fn rect(&mut self, x: i32, y: i32, width: i32, height: i32, clear: bool, flag: BackgroundFlag) {
+        let background = self.default_background;
+        for cy in y..y + height {
+            for cx in x..x + width {
+                if clear {
+                    if let Some(cell) = self.cell_mut(cx, cy) {
+                        cell.glyph = ' ';
+                    }
+                }
+                self.set_char_background(cx, cy, background, flag);
+            }
+        }
+    }
+
+    fn print_ex(
+        &mut self,
+        x: i32,
+        y: i32,
+        flag: BackgroundFlag,
+        alignment: TextAlignment,
+        text: &str,
+    ) {
+        for (i, line) in text.split('\n').enumerate() {
+            self.print_line(x, y + i as i32, flag, alignment, line);
+        }
+    }
+
+    fn print_rect(&mut self, x: i32, y: i32, width: i32, height: i32, text: &str) {
+        let area = Area {
+            x,
+            y,
+            width,
+            height,
+        };
+        self.print_rect_ex(area, BackgroundFlag::None, TextAlignment::Left, text);
+    }
+
+    fn print_rect_ex(
+        &mut self,
+        area: Area,
+        flag: BackgroundFlag,
+        alignment: TextAlignment,
+        text: &str,
+    ) {
+        for (i, line) in wrap(text, area.width).iter().enumerate() {
+            if area.height > 0 && i as i32 >= area.height {
+                break;
+            }
+            self.print_line(area.x, area.y + i as i32, flag, alignment, line);
+        }
+    }
+
+    fn get_height_rect(&self, _x: i32, _y: i32, width: i32, height: i32, text: &str) -> i32 {
+        let lines = wrap(text, width).len() as i32;
+        if height > 0 {
+            lines.min(height)
+        } else {
+            lines
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// A backend without a window: everything is drawn into memory and input comes
+/// from a queue of events. Once the queue runs dry the "window" counts as closed,
+/// which ends the game and menu loops.
+pub struct HeadlessBackend {
+    root: MemoryConsole,
+    events: VecDeque<Event>,
+    fullscreen: bool,
+    frames: usize,
+}
+
+impl HeadlessBackend {
+    pub fn new(width: i32, height: i32) -> Self {
+        HeadlessBackend {
+            root: MemoryConsole::new(width, height),
+            events: VecDeque::new(),
+            fullscreen: false,
+            frames: 0,
+        }
+    }
+
+    pub fn push_event(&mut self, event: Event) {
+        self.events.push_back(event);
+    }
+
+    /// what the last flushed frame looked like
+    pub fn screen(&self) -> &MemoryConsole {
+        &self.root
+    }
+
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+}
+
+impl Backend for HeadlessBackend {
+    fn offscreen(&self, width: i32, height: i32) -> Box<dyn Surface> {
+        Box::new(MemoryConsole::new(width, height))
+    }
+
+    fn root(&mut self) -> &mut dyn Surface {
+        &mut self.root
+    }
+
+    fn blit(
+        &mut self,
+        source: &dyn Surface,
+        source_pos: (i32, i32),
+        size: (i32, i32),
+        destination_pos: (i32, i32),
+        foreground_alpha: f32,
+        background_alpha: f32,
+    ) {
+        let source = source
+            .as_any()
+            .downcast_ref::<MemoryConsole>()
+            .expect("Can only blit surfaces created by the headless backend");
+        for dy in 0..size.1 {
+            for dx in 0..size.0 {
+                let from = match source.cell(source_pos.0 + dx, source_pos.1 + dy) {
+                    Some(cell) => *cell,
+                    None => continue,
+                };
+                if let Some(to) = self
+                    .root
+                    .cell_mut(destination_pos.0 + dx, destination_pos.1 + dy)
+                {
+                    if foreground_alpha > 0.0 {
+                        to.glyph = from.glyph;
+                        to.foreground = from.foreground;
+                    }
+                    if background_alpha > 0.0 {
+                        to.background = from.background;
+                    }
+                }
+            }
+        }
+    }
+
+    fn draw_menu_background(&mut self) {}
+
+    fn flush(&mut self) {
+        self.frames += 1;
+    }
+
+    // nothing is presented, there is nothing to slow down
+    fn set_fps(&mut self, _fps: i32) {}
+
+    fn window_closed(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    fn is_fullscreen(&self) -> bool {
+        self.fullscreen
+    }
+
+    fn set_fullscreen(&mut self, fullscreen: bool) {
+        self.fullscreen = fullscreen;
+    }
+
+    fn check_for_event(&mut self) -> Option<Event> {
+        self.events.pop_front()
+    }
+
+    fn wait_for_keypress(&mut self) -> Key {
+        while let Some(event) = self.events.pop_front() {
+            if let Event::Key(key) = event {
+                return key;
+            }
+        }
+        Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blitted_text_shows_up_on_screen() {
+        let mut backend = HeadlessBackend::new(20, 10);
+        let mut panel = backend.offscreen(20, 2);
+        panel.set_default_foreground(colors::RED);
+        panel.print_ex(1, 1, BackgroundFlag::None, TextAlignment::Left, "@ hi");
+        backend.blit(&*panel, (0, 0), (20, 2), (0, 8), 1.0, 1.0);
+        backend.flush();
+
+        let cell = backend.screen().cell(1, 9).unwrap();
+        assert_eq!(cell.glyph, '@');
+        assert_eq!(cell.foreground, colors::RED);
+        assert_eq!(backend.screen().line(9).trim_end(), " @ hi");
+        assert_eq!(backend.frames(), 1);
+    }
+
+    #[test]
+    fn wrapped_text_stays_inside_its_area() {
+        let mut screen = MemoryConsole::new(20, 10);
+        let area = Area {
+            x: 2,
+            y: 1,
+            width: 5,
+            height: 2,
+        };
+        screen.print_rect_ex(
+            area,
+            BackgroundFlag::None,
+            TextAlignment::Left,
+            "one two three",
+        );
+        assert_eq!(screen.line(1).trim_end(), "  one");
+        assert_eq!(screen.line(2).trim_end(), "  two");
+        assert_eq!(screen.line(3).trim(), "");
+    }
+}
@@ -0,0 +1,174 @@
+use crate::backend::{Area, Backend, Surface};
+
+use tcod::colors::Color;
+use tcod::console::*;
+use tcod::image::{self, Image};
+use tcod::input::{self, Event, Key};
+
+use std::any::Any;
+
+const MENU_BACKGROUND: &str = "menu_background.png";
+
+/// The real thing: a libtcod window.
+pub struct TcodBackend {
+    root: Root,
+    menu_background: Image,
+}
+
+impl TcodBackend {
+    pub fn new(root: Root) -> Self {
+        let menu_background = Image::from_file(MENU_BACKGROUND)
+            .ok()
+            .expect("Background image not found");
+        TcodBackend {
+            root,
+            menu_background,
+        }
+    }
+}
+
+impl Backend for TcodBackend {
+    fn offscreen(&self, width: i32, height: i32) -> Box<dyn Surface> {
+        Box::new(Offscreen::new(width, height))
+    }
+
+    fn root(&mut self) -> &mut dyn Surface {
+        &mut self.root
+    }
+
+    fn blit(
+        &mut self,
+        source: &dyn Surface,
+        source_pos: (i32, i32),
+        size: (i32, i32),
+        destination_pos: (i32, i32),
+        foreground_alpha: f32,
+        background_alpha: f32,
+    ) {
+        let source = source
+            .as_any()
+            .downcast_ref::<Offscreen>()
+            .expect("Can only blit surfaces created by the tcod backend");
+        blit(
+            source,
+            source_pos,
+            size,
+            &mut self.root,
+            destination_pos,
+            foreground_alpha,
+            background_alpha,
+        );
+    }
+
+    fn draw_menu_background(&mut self) {
+        // show the background image, at twice the regular console resolution
+        image::blit_2x(
+            &self.menu_background,
+            (0, 0),
+            (-1, -1),
+            &mut self.root,
+            (0, 0),
+        );
+    }
+
+    fn flush(&mut self) {
+        self.root.flush();
+    }
+
+    fn set_fps(&mut self, fps: i32) {
+        tcod::system::set_fps(fps);
+    }
+
+    fn window_closed(&self) -> bool {
+        self.root.window_closed()
+    }
+
+    fn is_fullscreen(&self) -> bool {
+        self.root.is_fullscreen()
+    }
+
+    fn set_fullscreen(&mut self, fullscreen: bool) {
+        self.root.set_fullscreen(fullscreen);
+    }
+
+    fn check_for_event(&mut self) -> Option<Event> {
+        input::check_for_event(input::MOUSE | input::KEY_PRESS).map(|e| e.1)
+    }
+
+    fn wait_for_keypress(&mut self) -> Key {
+        self.root.wait_for_keypress(true)
+    }
+}
+
+impl<C: Console + 'static> Surface for C {
+    fn width(&self) -> i32 {
+        Console::width(self)
+    }
+
+    fn height(&self) -> i32 {
+        Console::height(self)
+    }
+
+    fn clear(&mut self) {
+        Console::clear(self);
+    }
+
+    fn set_default_foreground(&mut self, color: Color) {
+        Console::set_default_foreground(self, color);
+    }
+
+    fn set_default_background(&mut self, color: Color) {
+        Console::set_default_background(self, color);
+    }
+
+    fn put_char(&mut self, x: i32, y: i32, glyph: char, flag: BackgroundFlag) {
+        Console::put_char(self, x, y, glyph, flag);
+    }
+
+    fn set_char_background(&mut self, x: i32, y: i32, color: Color, flag: BackgroundFlag) {
+        Console::set_char_background(self, x, y, color, flag);
+    }
+
+    fn rect(&mut self, x: i32, y: i32, width: i32, height: i32, clear: bool, flag: BackgroundFlag) {
+        Console::rect(self, x, y, width, height, clear, flag);
+    }
+
+    fn print_ex(
+        &mut self,
+        x: i32,
+        y: i32,
+        flag: BackgroundFlag,
+        alignment: TextAlignment,
+        text: &str,
+    ) {
+        Console::print_ex(self, x, y, flag, alignment, text);
+    }
+
+    fn print_rect(&mut self, x: i32, y: i32, width: i32, height: i32, text: &str) {
+        Console::print_rect(self, x, y, width, height, text);
+    }
+
+    fn print_rect_ex(
+        &mut self,
+        area: Area,
+        flag: BackgroundFlag,
+        alignment: TextAlignment,
+        text: &str,
+    ) {
+        let Area {
+            x,
+            y,
+            width,
+            height,
+        } = area;
+        Console::print_rect_ex(self, x, y, width, height, flag, alignment, text);
+    }
+
+    fn get_height_rect(&self, x: i32, y: i32, width: i32, height: i32, text: &str) -> i32 {
+        Console::get_height_rect(self, x, y, width, height, text)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
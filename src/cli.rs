@@ -0,0 +1,225 @@
+use crate::game::save::{is_slot_char, SaveFormat, MAX_SLOT_NAME};
+
+use tcod::console::FontLayout;
+
+use std::path::PathBuf;
+
+pub const USAGE: &str = "\
+Usage: rust-roguelike-tutorial [options]
+
+Starting a game (the main menu opens when none of these is given):
+  --new <slot>            start a new game saved in <slot> right away
+  --depth <level>         dungeon level the new game starts on (default 1)
+  --permadeath            delete the new game's save when the player dies
+  --load <slot>           continue the game saved in <slot>
+  --replay <file>         play a recorded game back at full speed
+  --autoplay <games>      let the bot play <games> games and report how they went
+
+Options:
+  --seed <number>         dungeon seed for new games (default: random), with
+                          --autoplay the first of consecutive seeds
+  --headless              run without a window, for --replay and --autoplay
+  --font <file>           font image to draw with (default: arial10x10.png)
+  --font-layout <layout>  how the font image is laid out: tcod, row or col
+  --fullscreen            open the window in fullscreen
+  --binary-saves          write saves in the compact binary format instead of JSON
+  --help                  show this message
+";
+
+const DEFAULT_FONT: &str = "arial10x10.png";
+
+/// What to do once the game is up.
+#[derive(Debug, PartialEq)]
+pub enum Start {
+    Menu,
+    New {
+        slot: String,
+        depth: u32,
+        permadeath: bool,
+    },
+    Load(String),
+    Replay(PathBuf),
+    Autoplay(u32),
+}
+
+/// Everything that can be set from the command line.
+#[derive(Debug)]
+pub struct Options {
+    pub start: Start,
+    pub seed: Option<u64>,
+    pub headless: bool,
+    pub font: String,
+    pub font_layout: FontLayout,
+    pub fullscreen: bool,
+    pub save_format: SaveFormat,
+    pub help: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            start: Start::Menu,
+            seed: None,
+            headless: false,
+            font: DEFAULT_FONT.into(),
+            font_layout: FontLayout::Tcod,
+            fullscreen: false,
+            save_format: SaveFormat::default(),
+            help: false,
+        }
+    }
+}
+
+/// read the options out of the arguments, without the program name
+pub fn parse<I: Iterator<Item = String>>(mut args: I) -> Result<Options, String> {
+    let mut options = Options::default();
+    let mut new_slot = None;
+    let mut depth = None;
+    let mut permadeath = false;
+    let mut starts = 0;
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{} needs a value", arg));
+        match arg.as_str() {
+            "--new" => {
+                new_slot = Some(slot_name(value()?)?);
+                starts += 1;
+            }
+            "--depth" => {
+                let level = value()?;
+                depth = match level.parse() {
+                    Ok(level) if level >= 1 => Some(level),
+                    _ => return Err(format!("Invalid dungeon level '{}'", level)),
+                };
+            }
+            "--permadeath" => permadeath = true,
+            "--load" => {
+                options.start = Start::Load(slot_name(value()?)?);
+                starts += 1;
+            }
+            "--replay" => {
+                options.start = Start::Replay(PathBuf::from(value()?));
+                starts += 1;
+            }
+            "--autoplay" => {
+                let games = value()?;
+                options.start = Start::Autoplay(
+                    games
+                        .parse()
+                        .map_err(|_| format!("Invalid number of games '{}'", games))?,
+                );
+                starts += 1;
+            }
+            "--seed" => {
+                let seed = value()?;
+                options.seed = Some(
+                    seed.parse()
+                        .map_err(|_| format!("Invalid seed '{}'", seed))?,
+                );
+            }
+            "--headless" => options.headless = true,
+            "--font" => options.font = value()?,
+            "--font-layout" => {
+                options.font_layout = match value()?.as_str() {
+                    "tcod" => FontLayout::Tcod,
+                    "row" => FontLayout::AsciiInRow,
+                    "col" => FontLayout::AsciiInCol,
+                    layout => return Err(format!("Unknown font layout '{}'", layout)),
+                }
+            }
+            "--fullscreen" => options.fullscreen = true,
+            "--binary-saves" => options.save_format = SaveFormat::Binary,
+            "--help" | "-h" => options.help = true,
+            _ => return Err(format!("Unknown option '{}'", arg)),
+        }
+    }
+
+    if starts > 1 {
+        return Err("Only one of --new, --load, --replay and --autoplay can be given".into());
+    }
+    match new_slot {
+        Some(slot) => {
+            options.start = Start::New {
+                slot,
+                depth: depth.unwrap_or(1),
+                permadeath,
+            }
+        }
+        None if depth.is_some() || permadeath => {
+            return Err("--depth and --permadeath only apply to a --new game".into())
+        }
+        None => {}
+    }
+    if options.seed.is_some() && matches!(options.start, Start::Load(_) | Start::Replay(_)) {
+        return Err(
+            "--seed only applies to new games, a loaded or replayed game keeps its own".into(),
+        );
+    }
+    if options.headless && !matches!(options.start, Start::Replay(_) | Start::Autoplay(_)) {
+        return Err(
+            "Without a window there is nobody to play, --headless needs --replay or --autoplay"
+                .into(),
+        );
+    }
+
+    Ok(options)
+}
+
+fn slot_name(slot: String) -> Result<String, String> {
+    let valid =
+        !slot.trim().is_empty() && slot.len() <= MAX_SLOT_NAME && slot.chars().all(is_slot_char);
+    if valid {
+        Ok(slot.trim().into())
+    } else {
+        Err(format!(
+            "Invalid save slot '{}': use up to {} letters, digits, spaces, '-' or '_'",
+            slot, MAX_SLOT_NAME
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_args(args: &[&str]) -> Result<Options, String> {
+        parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn new_game_options() {
+        let options = parse_args(&["--new", "hero", "--depth", "3", "--seed", "42"]).unwrap();
+        assert_eq!(
+            options.start,
+            Start::New {
+                slot: "hero".into(),
+                depth: 3,
+                permadeath: false
+            }
+        );
+        assert_eq!(options.seed, Some(42));
+        assert_eq!(parse_args(&[]).unwrap().start, Start::Menu);
+    }
+
+    #[test]
+    fn malformed_arguments_are_errors() {
+        assert!(parse_args(&["--bogus"]).is_err());
+        assert!(parse_args(&["--seed"]).is_err());
+        assert!(parse_args(&["--seed", "many"]).is_err());
+        assert!(parse_args(&["--new", "hero", "--depth", "0"]).is_err());
+        assert!(parse_args(&["--new", "a/b"]).is_err());
+        assert!(parse_args(&["--autoplay", "-1"]).is_err());
+        assert!(parse_args(&["--font-layout", "diagonal"]).is_err());
+    }
+
+    #[test]
+    fn conflicting_options_are_errors() {
+        assert!(parse_args(&["--new", "a", "--load", "b"]).is_err());
+        assert!(parse_args(&["--load", "a", "--seed", "1"]).is_err());
+        assert!(parse_args(&["--replay", "a.json", "--seed", "1"]).is_err());
+        assert!(parse_args(&["--depth", "2"]).is_err());
+        assert!(parse_args(&["--load", "a", "--permadeath"]).is_err());
+        assert!(parse_args(&["--headless", "--new", "a"]).is_err());
+        assert!(parse_args(&["--headless", "--autoplay", "3", "--seed", "1"]).is_ok());
+    }
+}
@@ -0,0 +1,85 @@
+pub mod bot;
+pub mod keymap;
+pub mod replay;
+
+use crate::game::Game;
+use crate::object::store::Objects;
+
+use serde::{Deserialize, Serialize};
+
+/// Everything the player can ask the game to do, independent of where the
+/// request came from (keyboard, replay, bot...).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Command {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    MoveUpLeft,
+    MoveUpRight,
+    MoveDownLeft,
+    MoveDownRight,
+    Wait,
+    PickUp,
+    Drop,
+    Inventory,
+    Fire,
+    CharacterInfo,
+    Descend,
+    Ascend,
+    ToggleFullscreen,
+    Exit,
+}
+
+impl Command {
+    /// the step a movement command makes, `None` for everything else
+    pub fn delta(self) -> Option<(i32, i32)> {
+        use Command::*;
+        match self {
+            MoveUp => Some((0, -1)),
+            MoveDown => Some((0, 1)),
+            MoveLeft => Some((-1, 0)),
+            MoveRight => Some((1, 0)),
+            MoveUpLeft => Some((-1, -1)),
+            MoveUpRight => Some((1, -1)),
+            MoveDownLeft => Some((-1, 1)),
+            MoveDownRight => Some((1, 1)),
+            _ => None,
+        }
+    }
+
+    /// the movement command that steps by `(dx, dy)`
+    pub fn step(dx: i32, dy: i32) -> Option<Command> {
+        use Command::*;
+        [
+            MoveUp,
+            MoveDown,
+            MoveLeft,
+            MoveRight,
+            MoveUpLeft,
+            MoveUpRight,
+            MoveDownLeft,
+            MoveDownRight,
+        ]
+        .into_iter()
+        .find(|command| command.delta() == Some((dx, dy)))
+    }
+}
+
+/// Makes the player's decisions in place of the keyboard and mouse,
+/// e.g. to play back a replay or to let the bot play.
+pub trait Driver {
+    /// the next command to play, `None` once the driver is done and the keyboard takes over
+    fn command(&mut self, game: &Game, objects: &Objects) -> Option<Command>;
+
+    /// pick one of the options of a menu, `None` closes it without a choice
+    fn menu_choice(&mut self, header: &str, options: &[String], game: &Game) -> Option<usize>;
+
+    /// pick a tile for a targeted effect, `None` cancels
+    fn target(
+        &mut self,
+        game: &Game,
+        objects: &Objects,
+        max_range: Option<f32>,
+    ) -> Option<(i32, i32)>;
+}
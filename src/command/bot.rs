@@ -0,0 +1,403 @@
+use crate::command::{Command, Driver};
+use crate::data;
+use crate::game::map::{is_out_of_bounds, DOWN_STAIRS};
+use crate::game::{play_bot, Game, Tcod};
+use crate::object::identify::Unidentified;
+use crate::object::item::Effect;
+use crate::object::status::StatusKind;
+use crate::object::store::Objects;
+use crate::object::Object;
+
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+
+/// the bot gives up on a game after this many turns
+const MAX_TURNS: u32 = 10_000;
+
+/// how far the bot reads scrolls at monsters, like the player's torch
+const SIGHT_RADIUS: f32 = 10.0;
+
+/// commands in a row that don't take a turn before the bot stops trying them,
+/// so that a scroll without a target or a full inventory can't stall the game
+const MAX_FREE_ACTIONS: u32 = 3;
+
+/// A simple player for balance testing: it explores every level, fights
+/// whatever it meets, drinks potions when hurt and reads scrolls at monsters.
+/// It plays through the same commands as the keyboard.
+pub struct Bot {
+    /// inventory slot to pick once the inventory menu opens
+    use_item: Option<usize>,
+    free_actions: u32,
+    level_ups: usize,
+}
+
+impl Bot {
+    pub fn new() -> Self {
+        Bot {
+            use_item: None,
+            free_actions: 0,
+            level_ups: 0,
+        }
+    }
+
+    /// play `command`, keeping count of the ones that don't take a turn
+    /// (the bot only fires when it has something to hit)
+    fn act(&mut self, command: Command) -> Option<Command> {
+        if matches!(command, Command::Wait | Command::Fire) || command.delta().is_some() {
+            self.free_actions = 0;
+        } else {
+            self.free_actions += 1;
+        }
+        Some(command)
+    }
+
+    /// open the inventory and use the item in slot `index` from the menu
+    fn use_item(&mut self, index: usize) -> Option<Command> {
+        self.use_item = Some(index);
+        self.act(Command::Inventory)
+    }
+}
+
+impl Default for Bot {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Driver for Bot {
+    fn command(&mut self, game: &Game, objects: &Objects) -> Option<Command> {
+        let player = &objects[game.player];
+        let fighter = match player.fighter.as_ref() {
+            Some(fighter) if player.alive && game.turns < MAX_TURNS => fighter,
+            _ => return self.act(Command::Exit),
+        };
+        let max_hp = player.max_hp();
+        let monster = closest_monster(game, objects);
+
+        if self.free_actions < MAX_FREE_ACTIONS {
+            // drink a potion when badly hurt
+            if fighter.hp < max_hp / 2 {
+                let potion = find_item(&player.inventory, game, |effect| match effect {
+                    Effect::Heal { .. } => true,
+                    Effect::Status {
+                        status,
+                        range: None,
+                    } => {
+                        status.kind == StatusKind::Regeneration
+                            && !fighter.has_status(StatusKind::Regeneration)
+                    }
+                    _ => false,
+                });
+                // no known potion helps, one of the unknown ones might
+                let potion =
+                    potion.or_else(|| find_unknown(&player.inventory, game, Unidentified::Potion));
+                if let Some(index) = potion {
+                    return self.use_item(index);
+                }
+            }
+
+            // read scrolls at monsters when the fight isn't going well
+            if let Some(monster) = monster.filter(|_| fighter.hp < max_hp * 3 / 4) {
+                let distance = player.distance_to(monster);
+                let has = |kind| monster.fighter.as_ref().is_some_and(|f| f.has_status(kind));
+                let scroll = find_item(&player.inventory, game, |effect| match effect {
+                    Effect::Lightning { range, .. } => distance <= range as f32,
+                    Effect::Confusion { range, .. } => {
+                        distance <= range as f32 && !has(StatusKind::Confusion)
+                    }
+                    // not so close that the player gets burned too
+                    Effect::Fireball { radius, .. } => distance > radius as f32,
+                    Effect::Status {
+                        status,
+                        range: Some(range),
+                    } => distance <= range as f32 && !has(status.kind),
+                    // potions that help in a fight, like haste
+                    Effect::Status {
+                        status,
+                        range: None,
+                    } => status.kind == StatusKind::Haste && !fighter.has_status(StatusKind::Haste),
+                    Effect::Heal { .. } | Effect::Identify => false,
+                });
+                let scroll =
+                    scroll.or_else(|| find_unknown(&player.inventory, game, Unidentified::Scroll));
+                if let Some(index) = scroll {
+                    return self.use_item(index);
+                }
+            }
+
+            // ready a bow or crossbow there is ammunition for
+            let launcher = |object: &Object| object.equipment.and_then(|e| e.launcher);
+            let has_ammo = |kind| {
+                player.inventory.iter().any(|o| {
+                    o.item
+                        .as_ref()
+                        .and_then(|i| i.ammo)
+                        .is_some_and(|a| a.kind == kind)
+                })
+            };
+            let ready = player
+                .inventory
+                .iter()
+                .filter(|o| o.equipment.is_some_and(|e| e.equipped))
+                .find_map(launcher)
+                .filter(|l| has_ammo(l.ammo));
+            if ready.is_none() {
+                let unused = player
+                    .inventory
+                    .iter()
+                    .position(|o| launcher(o).is_some_and(|l| has_ammo(l.ammo)));
+                if let Some(index) = unused {
+                    return self.use_item(index);
+                }
+            }
+
+            // shoot at monsters that aren't next to the player yet
+            if let (Some(launcher), Some(monster)) = (ready, monster) {
+                let distance = player.distance_to(monster);
+                if distance >= 2.0 && distance <= launcher.range as f32 {
+                    return self.act(Command::Fire);
+                }
+            }
+
+            let on_item = objects
+                .iter()
+                .any(|(_, o)| o.item.is_some() && o.pos() == player.pos());
+            if on_item && player.inventory.len() < 26 {
+                return self.act(Command::PickUp);
+            }
+        }
+
+        // hunt down the monsters seen so far, then collect the items, explore
+        // what's left and finally head down
+        let monsters = known(game, objects, |o| o.fighter.is_some() && o.ai.is_some());
+        let step = first_step(game, objects, |pos| monsters.contains(&pos));
+        let step = step.or_else(|| {
+            if player.inventory.len() >= 26 {
+                return None;
+            }
+            let items = known(game, objects, |o| o.item.is_some());
+            first_step(game, objects, |pos| items.contains(&pos))
+        });
+        let step = step.or_else(|| {
+            first_step(game, objects, |(x, y)| {
+                let tile = &game.map[x as usize][y as usize];
+                !tile.explored && !tile.blocked
+            })
+        });
+        let stairs = objects
+            .iter()
+            .map(|(_, o)| o)
+            .find(|o| o.name == DOWN_STAIRS)
+            .map(|o| o.pos());
+        if stairs == Some(player.pos()) && step.is_none() {
+            if self.free_actions < MAX_FREE_ACTIONS {
+                return self.act(Command::Descend);
+            }
+            return self.act(Command::Wait);
+        }
+        let step = step.or_else(|| first_step(game, objects, |pos| Some(pos) == stairs));
+
+        match step {
+            Some((x, y)) => {
+                let (px, py) = player.pos();
+                self.act(Command::step(x - px, y - py).unwrap_or(Command::Wait))
+            }
+            None => self.act(Command::Wait),
+        }
+    }
+
+    fn menu_choice(&mut self, _header: &str, options: &[String], _game: &Game) -> Option<usize> {
+        match self.use_item.take() {
+            Some(index) => Some(index),
+            // the only other menu during play is the level up, spread the points
+            None => {
+                self.level_ups += 1;
+                Some(self.level_ups % options.len().max(1))
+            }
+        }
+    }
+
+    fn target(
+        &mut self,
+        game: &Game,
+        objects: &Objects,
+        max_range: Option<f32>,
+    ) -> Option<(i32, i32)> {
+        let player = &objects[game.player];
+        closest_monster(game, objects)
+            .filter(|monster| max_range.is_none_or(|r| player.distance_to(monster) <= r))
+            .map(|monster| monster.pos())
+    }
+}
+
+/// where the objects that are `wanted` are, as far as explored tiles go
+fn known(game: &Game, objects: &Objects, wanted: impl Fn(&Object) -> bool) -> HashSet<(i32, i32)> {
+    objects
+        .iter()
+        .filter(|&(id, o)| {
+            id != game.player && wanted(o) && game.map[o.x as usize][o.y as usize].explored
+        })
+        .map(|(_, o)| o.pos())
+        .collect()
+}
+
+fn in_sight(player: &Object, object: &Object, game: &Game) -> bool {
+    player.distance_to(object) <= SIGHT_RADIUS
+        && game.map[object.x as usize][object.y as usize].explored
+}
+
+fn closest_monster<'a>(game: &Game, objects: &'a Objects) -> Option<&'a Object> {
+    let player = &objects[game.player];
+    objects
+        .iter()
+        .filter(|&(id, o)| {
+            id != game.player && o.fighter.is_some() && o.ai.is_some() && in_sight(player, o, game)
+        })
+        .map(|(_, o)| o)
+        .min_by(|a, b| player.distance_to(a).total_cmp(&player.distance_to(b)))
+}
+
+/// inventory slot of the first item whose effect is wanted, among the ones
+/// identified so far: the bot knows no more about its items than a player would
+fn find_item(inventory: &[Object], game: &Game, wanted: impl Fn(Effect) -> bool) -> Option<usize> {
+    inventory.iter().position(|object| {
+        object
+            .item
+            .as_ref()
+            .filter(|item| game.identities.disguise(&item.id).is_none())
+            .and_then(|item| item.effect)
+            .is_some_and(&wanted)
+    })
+}
+
+/// inventory slot of the first unidentified item that looks like `kind`, to try it out
+fn find_unknown(inventory: &[Object], game: &Game, kind: Unidentified) -> Option<usize> {
+    inventory.iter().position(|object| {
+        object
+            .item
+            .as_ref()
+            .filter(|item| game.identities.disguise(&item.id).is_some())
+            .and_then(|item| data::get().items.get(&item.id))
+            .is_some_and(|template| template.unidentified == Some(kind))
+    })
+}
+
+/// The first step towards the closest tile that `is_goal`, going over explored
+/// floor around monsters (breadth first, the bot doesn't need A*).
+fn first_step(
+    game: &Game,
+    objects: &Objects,
+    is_goal: impl Fn((i32, i32)) -> bool,
+) -> Option<(i32, i32)> {
+    let start = objects[game.player].pos();
+    let occupied: HashSet<(i32, i32)> = objects
+        .iter()
+        .filter(|&(id, o)| o.blocks && id != game.player)
+        .map(|(_, o)| o.pos())
+        .collect();
+
+    let mut came_from = HashMap::new();
+    let mut queue = VecDeque::from([start]);
+    while let Some(pos) = queue.pop_front() {
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                let next = (pos.0 + dx, pos.1 + dy);
+                if next == start
+                    || came_from.contains_key(&next)
+                    || is_out_of_bounds(next.0, next.1)
+                {
+                    continue;
+                }
+                let tile = &game.map[next.0 as usize][next.1 as usize];
+                if tile.blocked {
+                    continue;
+                }
+                if is_goal(next) {
+                    // walk back to the step right after the start
+                    let mut step = next;
+                    let mut previous = pos;
+                    while previous != start {
+                        step = previous;
+                        previous = came_from[&previous];
+                    }
+                    return Some(step);
+                }
+                if tile.explored && !occupied.contains(&next) {
+                    came_from.insert(next, pos);
+                    queue.push_back(next);
+                }
+            }
+        }
+    }
+    None
+}
+
+/// How one of the bot's games went.
+struct Outcome {
+    seed: u64,
+    depth: u32,
+    turns: u32,
+    /// `None` if the bot survived until it gave up
+    killed_by: Option<String>,
+}
+
+/// Let the bot play `games` games headless, from `first_seed` on, and print
+/// how far it got in each along with a summary.
+pub fn autoplay(tcod: &mut Tcod, first_seed: u64, games: u32) {
+    let mut outcomes = vec![];
+    for seed in (0..games as u64).map(|n| first_seed.wrapping_add(n)) {
+        let (game, objects) = play_bot(tcod, seed);
+        let outcome = Outcome {
+            seed,
+            depth: game.dungeon_level,
+            turns: game.turns,
+            killed_by: match objects[game.player].alive {
+                true => None,
+                false => Some(game.killed_by.unwrap_or_else(|| "unknown".into())),
+            },
+        };
+        match &outcome.killed_by {
+            Some(killer) => println!(
+                "seed {}: killed by {} on level {} after {} turns",
+                outcome.seed, killer, outcome.depth, outcome.turns
+            ),
+            None => println!(
+                "seed {}: still alive on level {} after {} turns",
+                outcome.seed, outcome.depth, outcome.turns
+            ),
+        }
+        outcomes.push(outcome);
+    }
+    if outcomes.is_empty() {
+        return;
+    }
+
+    let count = outcomes.len() as f32;
+    let average_depth = outcomes.iter().map(|o| o.depth as f32).sum::<f32>() / count;
+    let deepest = outcomes.iter().map(|o| o.depth).max().unwrap_or(0);
+    let average_turns = outcomes.iter().map(|o| o.turns as f32).sum::<f32>() / count;
+    println!(
+        "\n{} games: average depth {:.2}, deepest {}, average turns {:.0}",
+        outcomes.len(),
+        average_depth,
+        deepest,
+        average_turns
+    );
+
+    let mut causes: BTreeMap<&str, u32> = BTreeMap::new();
+    for outcome in &outcomes {
+        let cause = outcome.killed_by.as_deref().unwrap_or("nothing (gave up)");
+        *causes.entry(cause).or_default() += 1;
+    }
+    let mut causes: Vec<_> = causes.into_iter().collect();
+    causes.sort_by_key(|&(_, games)| Reverse(games));
+    println!("Causes of death:");
+    for (cause, games) in causes {
+        println!(
+            "  {:<20} {:>4} ({:.0}%)",
+            cause,
+            games,
+            games as f32 / count * 100.0
+        );
+    }
+}
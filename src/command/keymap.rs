@@ -0,0 +1,153 @@
+use crate::command::Command;
+
+use tcod::input::{Key, KeyCode};
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs::File;
+use std::io::{ErrorKind, Read};
+
+use serde::{Deserialize, Serialize};
+
+pub const KEYMAP_FILE: &str = "keymap.json";
+
+/// Ready-made sets of movement keys to start a keymap from.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Preset {
+    #[default]
+    Numpad,
+    Vi,
+    Wasd,
+}
+
+/// Contents of the keymap file: a preset plus any bindings overriding it, e.g.
+/// `{ "preset": "vi", "bindings": { "Ctrl+q": "Exit" } }`
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct KeymapFile {
+    #[serde(default)]
+    preset: Preset,
+    #[serde(default)]
+    bindings: HashMap<String, Command>,
+}
+
+/// Translates key presses into commands. Keys are named after the text they
+/// type ("g", "<") or their `KeyCode` ("Up", "NumPad8", "Escape"), optionally
+/// prefixed with modifiers ("Alt+Enter").
+pub struct Keymap {
+    bindings: HashMap<String, Command>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Keymap::preset(Preset::default())
+    }
+}
+
+impl Keymap {
+    pub fn preset(preset: Preset) -> Self {
+        use Command::*;
+
+        let mut bindings: Vec<(&str, Command)> = vec![
+            ("Alt+Enter", ToggleFullscreen),
+            ("Escape", Exit),
+            ("Up", MoveUp),
+            ("Down", MoveDown),
+            ("Left", MoveLeft),
+            ("Right", MoveRight),
+            ("Spacebar", Wait),
+            ("g", PickUp),
+            ("i", Inventory),
+            ("f", Fire),
+            ("<", Descend),
+            (">", Ascend),
+        ];
+        let movement: &[(&str, Command)] = match preset {
+            Preset::Numpad => &[
+                ("NumPad8", MoveUp),
+                ("NumPad2", MoveDown),
+                ("NumPad4", MoveLeft),
+                ("NumPad6", MoveRight),
+                ("NumPad7", MoveUpLeft),
+                ("NumPad9", MoveUpRight),
+                ("NumPad1", MoveDownLeft),
+                ("NumPad3", MoveDownRight),
+                ("NumPad5", Wait),
+                ("d", Drop),
+                ("c", CharacterInfo),
+            ],
+            Preset::Vi => &[
+                ("k", MoveUp),
+                ("j", MoveDown),
+                ("h", MoveLeft),
+                ("l", MoveRight),
+                ("y", MoveUpLeft),
+                ("u", MoveUpRight),
+                ("b", MoveDownLeft),
+                ("n", MoveDownRight),
+                (".", Wait),
+                ("d", Drop),
+                ("c", CharacterInfo),
+            ],
+            // "d" and "c" are movement keys here, so drop and character move away
+            Preset::Wasd => &[
+                ("w", MoveUp),
+                ("s", MoveDown),
+                ("a", MoveLeft),
+                ("d", MoveRight),
+                ("q", MoveUpLeft),
+                ("e", MoveUpRight),
+                ("z", MoveDownLeft),
+                ("c", MoveDownRight),
+                ("r", Drop),
+                ("x", CharacterInfo),
+            ],
+        };
+        bindings.extend_from_slice(movement);
+
+        Keymap {
+            bindings: bindings
+                .into_iter()
+                .map(|(key, command)| (key.to_string(), command))
+                .collect(),
+        }
+    }
+
+    /// read the keymap file, falling back to the default keys if there is none
+    pub fn load(path: &str) -> Result<Self, Box<dyn Error>> {
+        let mut json = String::new();
+        match File::open(path) {
+            Ok(mut file) => file.read_to_string(&mut json)?,
+            Err(ref e) if e.kind() == ErrorKind::NotFound => return Ok(Keymap::default()),
+            Err(e) => return Err(e.into()),
+        };
+        let config = serde_json::from_str::<KeymapFile>(&json)?;
+
+        let mut keymap = Keymap::preset(config.preset);
+        keymap.bindings.extend(config.bindings);
+        Ok(keymap)
+    }
+
+    pub fn command(&self, key: Key) -> Option<Command> {
+        key_name(key).and_then(|name| self.bindings.get(&name).copied())
+    }
+}
+
+fn key_name(key: Key) -> Option<String> {
+    let name = match key.code {
+        KeyCode::NoKey => return None,
+        KeyCode::Text => key.text().to_string(),
+        // no Text event comes while Ctrl is held, the letter only comes with the key press
+        KeyCode::Char if key.ctrl && key.printable != '\0' => key.printable.to_string(),
+        code => format!("{:?}", code),
+    };
+
+    let mut modifiers = String::new();
+    if key.ctrl {
+        modifiers.push_str("Ctrl+");
+    }
+    if key.alt {
+        modifiers.push_str("Alt+");
+    }
+    Some(modifiers + &name)
+}
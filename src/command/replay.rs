@@ -0,0 +1,128 @@
+use crate::command::{Command, Driver};
+use crate::game::Game;
+use crate::object::store::Objects;
+
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+/// replays are written in here, named after the save slot of the game
+const REPLAY_DIR: &str = "replays";
+
+/// One decision of the player, in the order the game asked for them.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Input {
+    Command(Command),
+    Menu(Option<usize>),
+    Target(Option<(i32, i32)>),
+}
+
+/// Everything needed to play a game again: the dungeon seed, the level the
+/// game started on and what the player did.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Replay {
+    pub seed: u64,
+    /// recordings from before games could start deeper down all began on level 1
+    #[serde(default = "first_level")]
+    pub depth: u32,
+    pub inputs: Vec<Input>,
+}
+
+fn first_level() -> u32 {
+    1
+}
+
+impl Replay {
+    pub fn new(seed: u64, depth: u32) -> Self {
+        Replay {
+            seed,
+            depth,
+            inputs: vec![],
+        }
+    }
+
+    /// where the replay of the game saved in `slot` goes
+    pub fn path(slot: &str) -> PathBuf {
+        PathBuf::from(REPLAY_DIR).join(slot).with_extension("json")
+    }
+
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let json = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let replay =
+            serde_json::from_str(&json).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Ok(replay)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+}
+
+/// Plays the inputs of a replay back, one at a time.
+pub struct Playback {
+    inputs: VecDeque<Input>,
+}
+
+impl Playback {
+    pub fn new(replay: Replay) -> Self {
+        Playback {
+            inputs: replay.inputs.into(),
+        }
+    }
+
+    /// the next input, if it is the kind the game asks for; anything else means
+    /// the game went a different way than when it was recorded, so stop there
+    fn next<T>(&mut self, expected: fn(Input) -> Option<T>) -> Option<T> {
+        let input = self.inputs.pop_front()?;
+        let value = expected(input);
+        if value.is_none() {
+            eprintln!("Replay out of sync: got {:?}, stopping playback", input);
+            self.inputs.clear();
+        }
+        value
+    }
+}
+
+impl Driver for Playback {
+    fn command(&mut self, _game: &Game, _objects: &Objects) -> Option<Command> {
+        loop {
+            let command = self.next(|input| match input {
+                Input::Command(command) => Some(command),
+                _ => None,
+            });
+            // older recordings end in the quit, which would end the game
+            // instead of handing it over to the player
+            if command != Some(Command::Exit) {
+                return command;
+            }
+        }
+    }
+
+    fn menu_choice(&mut self, _header: &str, _options: &[String], _game: &Game) -> Option<usize> {
+        self.next(|input| match input {
+            Input::Menu(choice) => Some(choice),
+            _ => None,
+        })
+        .flatten()
+    }
+
+    fn target(
+        &mut self,
+        _game: &Game,
+        _objects: &Objects,
+        _max_range: Option<f32>,
+    ) -> Option<(i32, i32)> {
+        self.next(|input| match input {
+            Input::Target(target) => Some(target),
+            _ => None,
+        })
+        .flatten()
+    }
+}
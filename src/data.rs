@@ -0,0 +1,123 @@
+use crate::game::map::SpawnTable;
+use crate::object::identify::check_appearances;
+use crate::object::item::ItemTemplate;
+use crate::object::loot::GOLD;
+use crate::object::monster::Monster;
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use serde::de::DeserializeOwned;
+
+pub const DATA_DIR: &str = "data";
+const MONSTERS_FILE: &str = "monsters.json";
+const ITEMS_FILE: &str = "items.json";
+const SPAWNS_FILE: &str = "spawns.json";
+
+/// Game content that lives in data files rather than in code.
+/// Loaded once at startup and read-only afterwards.
+pub struct Data {
+    pub monsters: HashMap<String, Monster>,
+    pub items: HashMap<String, ItemTemplate>,
+    pub spawns: SpawnTable,
+}
+
+static DATA: OnceLock<Data> = OnceLock::new();
+
+/// read every data file from `dir`, has to be called before any game starts
+pub fn load(dir: &str) -> Result<(), Box<dyn Error>> {
+    let dir = Path::new(dir);
+    let data = Data {
+        monsters: read_json(&dir.join(MONSTERS_FILE))?,
+        items: read_json(&dir.join(ITEMS_FILE))?,
+        spawns: read_json(&dir.join(SPAWNS_FILE))?,
+    };
+
+    let monster_ids: Vec<&str> = data.monsters.keys().map(|id| id.as_str()).collect();
+    let item_ids: Vec<&str> = data.items.keys().map(|id| id.as_str()).collect();
+    data.spawns
+        .validate(&monster_ids, &item_ids)
+        .map_err(|e| format!("{}: {}", SPAWNS_FILE, e))?;
+    if !data.items.contains_key(GOLD) {
+        return Err(format!("{}: there has to be a '{}' item", ITEMS_FILE, GOLD).into());
+    }
+    validate_counts(&data).map_err(|e| format!("{}: {}", ITEMS_FILE, e))?;
+    check_appearances(&data.items).map_err(|e| format!("{}: {}", ITEMS_FILE, e))?;
+    validate_launchers(&data).map_err(|e| format!("{}: {}", ITEMS_FILE, e))?;
+    validate_monster_energy(&data).map_err(|e| format!("{}: {}", MONSTERS_FILE, e))?;
+    validate_monster_items(&data, &item_ids).map_err(|e| format!("{}: {}", MONSTERS_FILE, e))?;
+
+    DATA.set(data)
+        .map_err(|_| "Game data is already loaded".into())
+}
+
+pub fn get() -> &'static Data {
+    DATA.get().expect("Game data has not been loaded")
+}
+
+/// monsters have to gain energy and pay some for an attack, or they would
+/// never act or never stop acting
+fn validate_monster_energy(data: &Data) -> Result<(), String> {
+    for (id, monster) in &data.monsters {
+        if monster.speed <= 0 {
+            return Err(format!("'{}' needs a speed above 0", id));
+        }
+        if monster.attack_cost <= 0 {
+            return Err(format!("'{}' needs an attack_cost above 0", id));
+        }
+    }
+    Ok(())
+}
+
+/// a shot has to take some energy
+fn validate_launchers(data: &Data) -> Result<(), String> {
+    for (id, template) in &data.items {
+        let launcher = template.equipment.and_then(|e| e.launcher);
+        if launcher.is_some_and(|l| l.cost <= 0) {
+            return Err(format!("'{}' needs a launcher cost above 0", id));
+        }
+    }
+    Ok(())
+}
+
+/// monsters can only spawn with items that exist and can be worn, and only
+/// drop items that exist
+fn validate_monster_items(data: &Data, item_ids: &[&str]) -> Result<(), String> {
+    for (id, monster) in &data.monsters {
+        monster
+            .loot
+            .validate(item_ids)
+            .map_err(|e| format!("'{}': {}", id, e))?;
+        for item in &monster.equipment {
+            match data.items.get(item) {
+                Some(template) if template.equipment.is_some() => {}
+                Some(_) => return Err(format!("'{}' can't wear '{}'", id, item)),
+                None => return Err(format!("'{}' wears unknown item '{}'", id, item)),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// items come in stacks of at least one, equipment never more than one
+fn validate_counts(data: &Data) -> Result<(), String> {
+    for (id, template) in &data.items {
+        if template.count < 1 || (template.equipment.is_some() && template.count > 1) {
+            return Err(format!("'{}' can't come {} at a time", id, template.count));
+        }
+    }
+    Ok(())
+}
+
+fn read_json<T: DeserializeOwned>(path: &Path) -> Result<T, Box<dyn Error>> {
+    let mut json = String::new();
+    File::open(path)
+        .and_then(|mut file| file.read_to_string(&mut json))
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    let value = serde_json::from_str(&json).map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok(value)
+}
@@ -1,56 +1,121 @@
 pub mod map;
-
+pub mod pathfinding;
+pub mod rng;
+pub mod save;
+
+use crate::backend::{Area, Backend, Surface};
+use crate::command::bot::Bot;
+use crate::command::keymap::Keymap;
+use crate::command::replay::{Input, Playback, Replay};
+use crate::command::{Command, Driver};
 use crate::config::*;
-use crate::game::map::{is_blocked, is_out_of_bounds, make_map, Map, MAP_HEIGHT, MAP_WIDTH};
+use crate::data;
+use crate::game::map::{
+    is_blocked, is_out_of_bounds, make_map, Level, Map, DOWN_STAIRS, MAP_HEIGHT, MAP_WIDTH,
+    UP_STAIRS,
+};
+use crate::game::rng::GameRng;
+use crate::game::save::{
+    is_slot_char, list_slots, load_game, save_game, slot_exists, LoadError, SaveFormat,
+    MAX_SLOT_NAME,
+};
 use crate::object::ai::ai_take_turn;
-use crate::object::equipment::{Equipment, Slot};
+use crate::object::damage::DamageKind;
+use crate::object::dice::Dice;
+use crate::object::equipment::{paper_doll, Equipment};
 use crate::object::fighter::{DeathCallback, Fighter};
+use crate::object::identify::Identities;
 use crate::object::item::Item;
-use crate::object::Object;
+use crate::object::loot::drop_loot;
+use crate::object::ranged::fire;
+use crate::object::status::{tick_statuses, StatusKind};
+use crate::object::store::{Entity, Objects};
+use crate::object::{Object, ACTION_COST};
 
 use tcod::colors::*;
 use tcod::console::*;
 
-use tcod::input::{self, Event, Key, Mouse};
+use tcod::input::{Event, Key, Mouse};
 use tcod::map::FovAlgorithm;
 use tcod::map::Map as FovMap;
 
+use std::collections::BTreeMap;
 use std::error::Error;
-use std::fs::File;
-use std::io::{Read, Write};
+
+use rand::{Rng, SeedableRng};
 
 use serde::{Deserialize, Serialize};
 
 pub struct Tcod {
-    pub root: Root,
-    pub con: Offscreen,
-    pub panel: Offscreen,
+    pub backend: Box<dyn Backend>,
+    pub con: Box<dyn Surface>,
+    pub panel: Box<dyn Surface>,
     pub fov: FovMap,
     pub key: Key,
     pub mouse: Mouse,
+    pub keymap: Keymap,
     pub ignore_next_event: bool,
+    /// makes the player's decisions instead of the keyboard and mouse while set
+    pub driver: Option<Box<dyn Driver>>,
 }
 
 impl Tcod {
-    pub fn new(root: Root) -> Self {
+    pub fn new(backend: Box<dyn Backend>, keymap: Keymap) -> Self {
         Tcod {
-            root,
-            con: Offscreen::new(MAP_WIDTH, MAP_HEIGHT),
-            panel: Offscreen::new(SCREEN_WIDTH, PANEL_HEIGHT),
+            con: backend.offscreen(MAP_WIDTH, MAP_HEIGHT),
+            panel: backend.offscreen(SCREEN_WIDTH, PANEL_HEIGHT),
+            backend,
             fov: FovMap::new(MAP_WIDTH, MAP_HEIGHT),
             key: Default::default(),
             mouse: Default::default(),
+            keymap,
             ignore_next_event: false,
+            driver: None,
         }
     }
+
+    /// no more input is coming: the window is gone and nothing drives the game
+    pub fn closed(&self) -> bool {
+        self.driver.is_none() && self.backend.window_closed()
+    }
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct Game {
     pub map: Map,
     pub messages: Messages,
-    pub inventory: Vec<Object>,
+    /// handle of the player among the current level's objects
+    pub player: Entity,
     pub dungeon_level: u32,
+    /// levels visited before, by depth
+    pub levels: BTreeMap<u32, Level>,
+    pub seed: u64,
+    pub rng: GameRng,
+    /// save slot the game is written to, taken from the file it was loaded from
+    #[serde(skip)]
+    pub slot: String,
+    /// how the save slot is written, also taken from the file
+    #[serde(skip)]
+    pub save_format: SaveFormat,
+    /// delete the save slot when the player dies
+    pub permadeath: bool,
+    /// everything the player did since the start, `None` for games older than replays
+    pub replay: Option<Replay>,
+    /// turns the player has taken so far
+    pub turns: u32,
+    /// what killed the player, once something has
+    pub killed_by: Option<String>,
+    /// what unidentified items look like and which ones the player knows
+    pub identities: Identities,
+}
+
+impl Game {
+    /// remember a decision of the player for the replay
+    pub fn record(&mut self, input: Input) {
+        if let Some(replay) = self.replay.as_mut() {
+            replay.inputs.push(input);
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize)]
@@ -72,11 +137,18 @@ impl Messages {
     }
 }
 
-const SAVEGAME_FILE: &str = "savegame.dat";
+/// save slot of a game that carries on where a replay ended
+const REPLAY_SLOT: &str = "replay";
+
+/// slot name of the bot's games, which are never written
+const BOT_SLOT: &str = "bot";
 
 const FOV_ALGO: FovAlgorithm = FovAlgorithm::Basic; // default FOV algorithm
 const FOV_LIGHT_WALLS: bool = true; // light walls or not
-const TORCH_RADIUS: i32 = 10;
+pub const TORCH_RADIUS: i32 = 10;
+
+/// the player's damage roll without a weapon
+pub const FISTS: Dice = Dice::new(1, 3);
 
 const COLOR_DARK_WALL: Color = Color { r: 0, g: 0, b: 100 };
 const COLOR_LIGHT_WALL: Color = Color {
@@ -96,7 +168,7 @@ const COLOR_LIGHT_GROUND: Color = Color {
 };
 
 /// move by the given amount, if the destination is not blocked
-pub fn move_by(id: usize, dx: i32, dy: i32, game: &mut Game, objects: &mut [Object]) {
+pub fn move_by(id: Entity, dx: i32, dy: i32, game: &mut Game, objects: &mut Objects) {
     let pos = objects[id].pos();
 
     let new_x = pos.0 + dx;
@@ -109,71 +181,133 @@ pub fn move_by(id: usize, dx: i32, dy: i32, game: &mut Game, objects: &mut [Obje
     objects[id].set_pos(new_x, new_y);
 }
 
-fn player_move_or_attack(dx: i32, dy: i32, game: &mut Game, objects: &mut [Object]) {
-    let pos = objects[PLAYER].pos();
+/// step or attack in the given direction, returning the energy it took
+fn player_move_or_attack(dx: i32, dy: i32, game: &mut Game, objects: &mut Objects) -> i32 {
+    let pos = objects[game.player].pos();
     let new_pos = (pos.0 + dx, pos.1 + dy);
 
     let target_id = objects
         .iter()
-        .position(|enemy| enemy.fighter.is_some() && enemy.pos() == new_pos);
+        .find(|&(id, enemy)| id != game.player && enemy.fighter.is_some() && enemy.pos() == new_pos)
+        .map(|(id, _)| id);
 
     if let Some(id) = target_id {
-        let (player, monster) = mut_two(PLAYER, id, objects);
-        player.attack(monster, game);
+        let (player, monster) = objects.get_two_mut(game.player, id);
+        let cost = player.attack_cost();
+        if player.attack(monster, game) {
+            drop_loot(id, game, objects);
+        }
+        cost
     } else {
-        move_by(PLAYER, dx, dy, game, objects);
+        move_by(game.player, dx, dy, game, objects);
+        ACTION_COST
     }
 }
 
-/// Mutably borrow two *separate* elements from the slice
-fn mut_two<T>(first_id: usize, second_id: usize, items: &mut [T]) -> (&mut T, &mut T) {
-    assert!(first_id < second_id);
-    let (first_slice, second_slice) = items.split_at_mut(second_id);
-    (&mut first_slice[first_id], &mut second_slice[0])
-}
-
 #[derive(Clone, Copy, Debug, PartialEq)]
-enum PlayerAction {
-    TookTurn,
+pub enum PlayerAction {
+    /// the action took this much energy
+    TookTurn(i32),
     DidntTakeTurn,
     Exit,
 }
 
-fn pick_item_up(id: usize, game: &mut Game, objects: &mut Vec<Object>) {
-    if game.inventory.len() >= 26 {
+fn pick_item_up(id: Entity, game: &mut Game, objects: &mut Objects) {
+    if objects[id].gold > 0 {
+        let gold = objects.remove(id).unwrap();
+        game.messages
+            .add(format!("You pick up {}.", gold.name), GOLD);
+        objects[game.player].gold += gold.gold;
+    } else if let Some(stack) = objects[game.player]
+        .inventory
+        .iter()
+        .position(|item| item.stacks_with(&objects[id]))
+    {
+        let item = objects.remove(id).unwrap();
+        game.messages.add(
+            format!("You've just picked up {}!", some(&item, game)),
+            GREEN,
+        );
+        let count = item.item.map_or(1, |i| i.count);
+        let stack = &mut objects[game.player].inventory[stack];
+        stack.item.as_mut().unwrap().count += count;
+    } else if objects[game.player].inventory.len() >= 26 {
         game.messages.add(
-            format!("Your inventory is full, can't pick up {}", objects[id].name),
+            format!(
+                "Your inventory is full, can't pick up {}",
+                game.identities.name(&objects[id])
+            ),
             RED,
         );
     } else {
-        let item = objects.swap_remove(id);
-        game.messages
-            .add(format!("You've just picked up a {}!", item.name), GREEN);
-        let slot = item.equipment.map(|e| e.slot);
-        game.inventory.push(item);
+        let item = objects.remove(id).unwrap();
+        game.messages.add(
+            format!("You've just picked up {}!", some(&item, game)),
+            GREEN,
+        );
+        let inventory = &mut objects[game.player].inventory;
+        let slot = item
+            .equipment
+            .and_then(|e| Equipment::free_slot(e, inventory));
+        inventory.push(item);
 
+        // put it on right away if nothing has to come off for it
         if let Some(slot) = slot {
-            if Equipment::get_equipped_in_slot(slot, &game.inventory).is_none() {
-                let index = game.inventory.len() - 1;
-                game.inventory[index].equip(&mut game.messages);
-            }
+            let index = inventory.len() - 1;
+            inventory[index].equipment.as_mut().unwrap().slot = slot;
+            inventory[index].equip(&mut game.messages);
         }
     }
 }
 
-fn drop_item(id: usize, game: &mut Game, objects: &mut Vec<Object>) {
-    let mut item = game.inventory.remove(id);
+/// "a healing potion", or "3 healing potions" for a stack of them, as far
+/// as the player knows what they are
+fn some(item: &Object, game: &Game) -> String {
+    match item.item.as_ref() {
+        Some(i) if i.count > 1 => game.identities.name(item),
+        _ => format!("a {}", game.identities.name(item)),
+    }
+}
+
+/// drop an item, or as much of its stack as the player asks for
+fn drop_item(id: usize, tcod: &mut Tcod, game: &mut Game, objects: &mut Objects) {
+    let count = objects[game.player].inventory[id]
+        .item
+        .as_ref()
+        .map_or(1, |i| i.count);
+    let mut item = if count > 1 {
+        let choice = play_menu(
+            "Drop how many?\n",
+            &["Just one", "All of them"],
+            INVENTORY_WIDTH,
+            tcod,
+            game,
+        );
+        match choice {
+            Some(0) => {
+                let stack = objects[game.player].inventory[id].item.as_mut().unwrap();
+                stack.count -= 1;
+                let mut one = Item::create(&stack.id, 0, 0);
+                one.item.as_mut().unwrap().count = 1;
+                one
+            }
+            Some(1) => objects[game.player].inventory.remove(id),
+            _ => return,
+        }
+    } else {
+        objects[game.player].inventory.remove(id)
+    };
     if item.equipment.is_some() {
         item.dequip(&mut game.messages);
     }
-    item.set_pos(objects[PLAYER].x, objects[PLAYER].y);
+    item.set_pos(objects[game.player].x, objects[game.player].y);
     game.messages
-        .add(format!("You dropped a {}.", item.name), YELLOW);
-    objects.push(item);
+        .add(format!("You dropped {}.", some(&item, game)), YELLOW);
+    objects.insert(item);
 }
 
-fn level_up(tcod: &mut Tcod, game: &mut Game, objects: &mut [Object]) {
-    let player = &mut objects[PLAYER];
+fn level_up(tcod: &mut Tcod, game: &mut Game, objects: &mut Objects) {
+    let player = &mut objects[game.player];
     let level_up_xp = player.level_up_xp();
 
     if player.fighter.as_ref().map_or(0, |f| f.xp) >= level_up_xp {
@@ -188,8 +322,8 @@ fn level_up(tcod: &mut Tcod, game: &mut Game, objects: &mut [Object]) {
 
         let fighter = player.fighter.as_mut().unwrap();
         let mut choice = None;
-        while choice.is_none() {
-            choice = menu(
+        while choice.is_none() && !tcod.closed() {
+            choice = play_menu(
                 "Level up! Choose a stat to taise:\n",
                 &[
                     format!("Constitution (+20 HP, from {})", fighter.base_max_hp),
@@ -198,10 +332,16 @@ fn level_up(tcod: &mut Tcod, game: &mut Game, objects: &mut [Object]) {
                 ],
                 LEVEL_SCREEN_WIDTH,
                 tcod,
+                game,
             )
         }
+        let choice = match choice {
+            Some(choice) => choice,
+            // the window went away before a stat was picked
+            None => return,
+        };
         fighter.xp -= level_up_xp;
-        match choice.unwrap() {
+        match choice {
             0 => {
                 fighter.base_max_hp += 20;
                 fighter.hp += 20;
@@ -225,22 +365,21 @@ fn menu<T: AsRef<str>>(header: &str, options: &[T], width: i32, tcod: &mut Tcod)
     let header_height = if header.is_empty() {
         0
     } else {
-        tcod.root
+        tcod.backend
+            .root()
             .get_height_rect(0, 0, width, SCREEN_HEIGHT, header)
     };
     let height = options.len() as i32 + header_height;
 
-    let mut window = Offscreen::new(width, height);
+    let mut window = tcod.backend.offscreen(width, height);
     window.set_default_foreground(WHITE);
-    window.print_rect_ex(
-        0,
-        0,
+    let area = Area {
+        x: 0,
+        y: 0,
         width,
         height,
-        BackgroundFlag::None,
-        TextAlignment::Left,
-        header,
-    );
+    };
+    window.print_rect_ex(area, BackgroundFlag::None, TextAlignment::Left, header);
 
     for (index, text) in options.iter().enumerate() {
         let letter = (b'a' + index as u8) as char;
@@ -250,24 +389,17 @@ fn menu<T: AsRef<str>>(header: &str, options: &[T], width: i32, tcod: &mut Tcod)
             header_height + index as i32,
             BackgroundFlag::None,
             TextAlignment::Left,
-            text,
+            &text,
         );
     }
 
     let x = (SCREEN_WIDTH - width) / 2;
     let y = (SCREEN_HEIGHT - height) / 2;
-    blit(
-        &window,
-        (0, 0),
-        (width, height),
-        &mut tcod.root,
-        (x, y),
-        1.0,
-        0.7,
-    );
+    tcod.backend
+        .blit(&*window, (0, 0), (width, height), (x, y), 1.0, 0.7);
 
-    tcod.root.flush();
-    let key = tcod.root.wait_for_keypress(true);
+    tcod.backend.flush();
+    let key = tcod.backend.wait_for_keypress();
 
     tcod.ignore_next_event = true;
 
@@ -283,12 +415,82 @@ fn menu<T: AsRef<str>>(header: &str, options: &[T], width: i32, tcod: &mut Tcod)
     }
 }
 
+/// a menu during play: the driver picks if there is one, and the choice goes into the replay
+fn play_menu<T: AsRef<str>>(
+    header: &str,
+    options: &[T],
+    width: i32,
+    tcod: &mut Tcod,
+    game: &mut Game,
+) -> Option<usize> {
+    let choice = match tcod.driver.as_mut() {
+        Some(driver) => {
+            let options: Vec<String> = options.iter().map(|o| o.as_ref().to_string()).collect();
+            driver.menu_choice(header, &options, game)
+        }
+        None => menu(header, options, width, tcod),
+    };
+    game.record(Input::Menu(choice));
+    choice
+}
+
 fn msgbox(text: &str, width: i32, tcod: &mut Tcod) {
     let options: Vec<&str> = vec![];
     menu(text, &options, width, tcod);
 }
 
-fn inventory_menu(inventory: &[Object], header: &str, tcod: &mut Tcod) -> Option<usize> {
+/// let the player type a line of up to `max_length` accepted characters,
+/// `None` if cancelled with Escape
+fn text_input(
+    header: &str,
+    width: i32,
+    max_length: usize,
+    accept: fn(char) -> bool,
+    tcod: &mut Tcod,
+) -> Option<String> {
+    use tcod::input::KeyCode::{Backspace, Enter, Escape, NumPadEnter};
+
+    let mut text = String::new();
+    while !tcod.backend.window_closed() {
+        let mut window = tcod.backend.offscreen(width, 3);
+        window.set_default_foreground(WHITE);
+        window.print_ex(0, 0, BackgroundFlag::None, TextAlignment::Left, header);
+        window.print_ex(
+            0,
+            2,
+            BackgroundFlag::None,
+            TextAlignment::Left,
+            &format!("> {}_", text),
+        );
+
+        let x = (SCREEN_WIDTH - width) / 2;
+        let y = (SCREEN_HEIGHT - 3) / 2;
+        tcod.backend
+            .blit(&*window, (0, 0), (width, 3), (x, y), 1.0, 0.7);
+        tcod.backend.flush();
+
+        let key = tcod.backend.wait_for_keypress();
+        tcod.ignore_next_event = true;
+        match key.code {
+            Enter | NumPadEnter if !text.trim().is_empty() => return Some(text.trim().into()),
+            Escape => return None,
+            Backspace => {
+                text.pop();
+            }
+            _ if accept(key.printable) && text.len() < max_length => text.push(key.printable),
+            _ => {}
+        }
+    }
+    None
+}
+
+pub fn inventory_menu(
+    header: &str,
+    tcod: &mut Tcod,
+    game: &mut Game,
+    objects: &Objects,
+) -> Option<usize> {
+    let inventory = &objects[game.player].inventory;
     let options = if inventory.len() == 0 {
         vec!["Inventory is empty.".into()]
     } else {
@@ -296,14 +498,15 @@ fn inventory_menu(inventory: &[Object], header: &str, tcod: &mut Tcod) -> Option
             .iter()
             .map(|item| match item.equipment {
                 Some(equipment) if equipment.equipped => {
-                    format!("{} (on {})", item.name, equipment.slot)
+                    format!("{} (on {})", item.name, equipment.worn_on())
                 }
-                _ => item.name.clone(),
+                _ => game.identities.name(item),
             })
             .collect()
     };
 
-    let index = menu(header, &options, INVENTORY_WIDTH, tcod);
+    let header = format!("{}\n\n{}", paper_doll(inventory), header);
+    let index = play_menu(&header, &options, INVENTORY_WIDTH, tcod, game);
 
     if inventory.len() > 0 {
         index
@@ -312,10 +515,28 @@ fn inventory_menu(inventory: &[Object], header: &str, tcod: &mut Tcod) -> Option
     }
 }
 
+/// how far the player sees, which is not far at all when blind
+fn sight_radius(player: &Object) -> i32 {
+    let blind = player
+        .fighter
+        .as_ref()
+        .is_some_and(|f| f.has_status(StatusKind::Blindness));
+    if blind {
+        1
+    } else {
+        TORCH_RADIUS
+    }
+}
+
 fn vision_update(tcod: &mut Tcod, map: &mut Map, player: &Object) {
     // recompute fov
-    tcod.fov
-        .compute_fov(player.x, player.y, TORCH_RADIUS, FOV_LIGHT_WALLS, FOV_ALGO);
+    tcod.fov.compute_fov(
+        player.x,
+        player.y,
+        sight_radius(player),
+        FOV_LIGHT_WALLS,
+        FOV_ALGO,
+    );
 
     // explore map
     for y in 0..MAP_HEIGHT {
@@ -328,7 +549,7 @@ fn vision_update(tcod: &mut Tcod, map: &mut Map, player: &Object) {
 }
 
 fn render_bar(
-    panel: &mut Offscreen,
+    panel: &mut dyn Surface,
     x: i32,
     y: i32,
     total_width: i32,
@@ -356,11 +577,11 @@ fn render_bar(
         y,
         BackgroundFlag::None,
         TextAlignment::Center,
-        format!("{}: {}/{}", name, value, maximum),
+        &format!("{}: {}/{}", name, value, maximum),
     );
 }
 
-pub fn render_all(tcod: &mut Tcod, game: &Game, objects: &[Object]) {
+pub fn render_all(tcod: &mut Tcod, game: &Game, objects: &Objects) {
     // render map
     for y in 0..MAP_HEIGHT {
         for x in 0..MAP_WIDTH {
@@ -382,21 +603,21 @@ pub fn render_all(tcod: &mut Tcod, game: &Game, objects: &[Object]) {
 
     let mut to_draw: Vec<_> = objects
         .iter()
+        .map(|(_, o)| o)
         .filter(|o| o.always_visible || tcod.fov.is_in_fov(o.x, o.y))
         .collect();
     to_draw.sort_by(|lhs, rhs| lhs.blocks.cmp(&rhs.blocks));
 
     // render objects
     for obj in to_draw {
-        obj.draw(&mut tcod.con);
+        obj.draw(&mut *tcod.con, game.identities.color(obj));
     }
 
     // blit the contents of "con" to the root console and present it
-    blit(
-        &tcod.con,
+    tcod.backend.blit(
+        &*tcod.con,
         (0, 0),
         (MAP_WIDTH, MAP_HEIGHT),
-        &mut tcod.root,
         (0, 0),
         1.0,
         1.0,
@@ -405,11 +626,11 @@ pub fn render_all(tcod: &mut Tcod, game: &Game, objects: &[Object]) {
     tcod.panel.set_default_background(BLACK);
     tcod.panel.clear();
 
-    let hp = objects[PLAYER].fighter.map_or(0, |f| f.hp);
-    let max_hp = objects[PLAYER].max_hp(game);
+    let hp = objects[game.player].fighter.as_ref().map_or(0, |f| f.hp);
+    let max_hp = objects[game.player].max_hp();
 
     render_bar(
-        &mut tcod.panel,
+        &mut *tcod.panel,
         1,
         1,
         BAR_WIDTH,
@@ -425,16 +646,37 @@ pub fn render_all(tcod: &mut Tcod, game: &Game, objects: &[Object]) {
         3,
         BackgroundFlag::None,
         TextAlignment::Left,
-        format!("Dungeon level: {}", game.dungeon_level),
+        &format!("Dungeon level: {}", game.dungeon_level),
     );
 
+    // the player's statuses with the turns they have left, as many as fit
+    let statuses = objects[game.player]
+        .fighter
+        .as_ref()
+        .map_or(&[][..], |f| &f.statuses[..]);
+    let (mut x, mut y) = (1, 4);
+    for status in statuses {
+        let text = format!("{} {}", status.kind, status.turns);
+        if x + text.len() as i32 > BAR_WIDTH + 1 {
+            x = 1;
+            y += 1;
+        }
+        if y >= PANEL_HEIGHT {
+            break;
+        }
+        tcod.panel.set_default_foreground(status.kind.color());
+        tcod.panel
+            .print_ex(x, y, BackgroundFlag::None, TextAlignment::Left, &text);
+        x += text.len() as i32 + 1;
+    }
+
     tcod.panel.set_default_foreground(LIGHT_GREY);
     tcod.panel.print_ex(
         1,
         0,
         BackgroundFlag::None,
         TextAlignment::Left,
-        get_names_under_mouse(tcod.mouse, objects, &tcod.fov),
+        &get_names_under_mouse(tcod.mouse, game, objects, &tcod.fov),
     );
 
     let mut y = MSG_HEIGHT as i32;
@@ -448,56 +690,62 @@ pub fn render_all(tcod: &mut Tcod, game: &Game, objects: &[Object]) {
         tcod.panel.print_rect(MSG_X, y, MSG_WIDTH, 0, msg);
     }
 
-    blit(
-        &tcod.panel,
+    tcod.backend.blit(
+        &*tcod.panel,
         (0, 0),
         (SCREEN_WIDTH, SCREEN_HEIGHT),
-        &mut tcod.root,
         (0, PANEL_Y),
         1.0,
         1.0,
     );
 }
 
-fn get_names_under_mouse(mouse: Mouse, objects: &[Object], fov_map: &FovMap) -> String {
+fn get_names_under_mouse(mouse: Mouse, game: &Game, objects: &Objects, fov_map: &FovMap) -> String {
     let (x, y) = (mouse.cx as i32, mouse.cy as i32);
 
     let names = objects
         .iter()
+        .map(|(_, o)| o)
         .filter(|o| o.pos() == (x, y) && fov_map.is_in_fov(o.x, o.y))
-        .map(|o| o.name.clone())
+        .map(|o| game.identities.name(o))
         .collect::<Vec<_>>();
 
     names.join(", ")
 }
 
-fn handle_keys(tcod: &mut Tcod, game: &mut Game, objects: &mut Vec<Object>) -> PlayerAction {
-    use tcod::input::Key;
-    use tcod::input::KeyCode::*;
+/// translate the last key press into a command, using the player's keymap
+fn handle_keys(tcod: &Tcod) -> Option<Command> {
+    tcod.keymap.command(tcod.key)
+}
+
+/// carry out a command, no matter where it came from
+pub fn play_command(
+    command: Command,
+    tcod: &mut Tcod,
+    game: &mut Game,
+    objects: &mut Objects,
+) -> PlayerAction {
     use PlayerAction::*;
 
-    match (tcod.key, tcod.key.text(), objects[PLAYER].alive) {
-        (
-            Key {
-                code: Enter,
-                alt: true,
-                ..
-            },
-            _,
-            _,
-        ) => {
-            // Alt+Enter: toggle fullscreen
-            let fullscreen = tcod.root.is_fullscreen();
-            tcod.root.set_fullscreen(!fullscreen);
-            return DidntTakeTurn;
-        }
-        (Key { code: Escape, .. }, _, _) => return Exit, // exit game
+    // quitting is left out, so a game that was continued replays past it
+    if command != Command::Exit {
+        game.record(Input::Command(command));
+    }
+    let player_pos = objects[game.player].pos();
+    match (command, objects[game.player].alive) {
+        (Command::ToggleFullscreen, _) => {
+            let fullscreen = tcod.backend.is_fullscreen();
+            tcod.backend.set_fullscreen(!fullscreen);
+            DidntTakeTurn
+        }
+        (Command::Exit, _) => Exit, // exit game
 
         // pick up an item
-        (Key { code: Text, .. }, "g", true) => {
+        (Command::PickUp, true) => {
             let item_id = objects
                 .iter()
-                .position(|o| o.pos() == objects[PLAYER].pos() && o.item.is_some());
+                .find(|(_, o)| o.pos() == player_pos && o.item.is_some())
+                .map(|(id, _)| id);
             if let Some(id) = item_id {
                 pick_item_up(id, game, objects)
             }
@@ -505,24 +753,26 @@ fn handle_keys(tcod: &mut Tcod, game: &mut Game, objects: &mut Vec<Object>) -> P
         }
 
         // drop an item
-        (Key { code: Text, .. }, "d", true) => {
+        (Command::Drop, true) => {
             if let Some(choice) = inventory_menu(
-                &game.inventory,
                 "Press the key next to an item to drop it, or any other to cancel\n",
                 tcod,
+                game,
+                objects,
             ) {
-                drop_item(choice, game, objects);
+                drop_item(choice, tcod, game, objects);
             }
 
             DidntTakeTurn
         }
 
         // open inventory and optionally use the item
-        (Key { code: Text, .. }, "i", true) => {
+        (Command::Inventory, true) => {
             if let Some(choice) = inventory_menu(
-                &game.inventory,
                 "Press the key next to an item to use it, or any other to cancel\n",
                 tcod,
+                game,
+                objects,
             ) {
                 Item::use_item(choice, tcod, game, objects)
             }
@@ -530,40 +780,72 @@ fn handle_keys(tcod: &mut Tcod, game: &mut Game, objects: &mut Vec<Object>) -> P
             DidntTakeTurn
         }
 
+        // shoot with the equipped bow or crossbow
+        (Command::Fire, true) => match fire(tcod, game, objects) {
+            Some(cost) => TookTurn(cost),
+            None => DidntTakeTurn,
+        },
+
         // show character information
-        (Key { code: Text, .. }, "c", true) => {
-            let player = &objects[PLAYER];
+        (Command::CharacterInfo, true) => {
+            let player = &objects[game.player];
             let level = player.level;
             let level_up_xp = player.level_up_xp();
             if let Some(fighter) = player.fighter.as_ref() {
+                let resistances: Vec<String> = DamageKind::ALL
+                    .iter()
+                    .map(|&kind| (kind, player.resistance(kind)))
+                    .filter(|&(_, percent)| percent != 0)
+                    .map(|(kind, percent)| format!("{} {}%", kind, percent))
+                    .collect();
+                let resistances = if resistances.is_empty() {
+                    "none".to_string()
+                } else {
+                    resistances.join(", ")
+                };
                 let msg = format!(
                     "Character information
 
 Level: {}
 Experience: {}
 Experience to level up: {}
+Gold: {}
 
 Maximum HP: {}
-Attack: {}
-Defense: {}",
+Attack: {} + {}
+Accuracy: {}
+Defense: {}
+Evasion: {}
+Resistances: {}
+
+{}",
                     level,
                     fighter.xp,
                     level_up_xp,
-                    player.max_hp(game),
-                    player.power(game),
-                    player.defense(game),
+                    player.gold,
+                    player.max_hp(),
+                    player.damage(),
+                    player.power(),
+                    player.accuracy(),
+                    player.defense(),
+                    player.evasion(),
+                    resistances,
+                    paper_doll(&player.inventory),
                 );
-                msgbox(&msg, CHARACTER_SCREEN_WIDTH, tcod);
+                // nobody is looking when a driver plays
+                if tcod.driver.is_none() {
+                    msgbox(&msg, CHARACTER_SCREEN_WIDTH, tcod);
+                }
             }
 
             DidntTakeTurn
         }
 
         // go down stairs if the player is on them
-        (Key { code: Text, .. }, "<", true) => {
+        (Command::Descend, true) => {
             let player_on_stairs = objects
                 .iter()
-                .any(|o| o.pos() == objects[PLAYER].pos() && o.name == "stairs");
+                .any(|(_, o)| o.pos() == player_pos && o.name == DOWN_STAIRS);
             if player_on_stairs {
                 next_level(tcod, game, objects);
             }
@@ -571,46 +853,46 @@ Defense: {}",
             DidntTakeTurn
         }
 
-        // do nothing i. e. wait for the monster to come to you
-        (Key { code: Spacebar, .. }, _, true) | (Key { code: NumPad5, .. }, _, true) => TookTurn,
+        // go back up if the player is on up stairs
+        (Command::Ascend, true) => {
+            let player_on_stairs = objects
+                .iter()
+                .any(|(_, o)| o.pos() == player_pos && o.name == UP_STAIRS);
+            if player_on_stairs {
+                previous_level(tcod, game, objects);
+            }
 
-        // movement keys
-        (Key { code: Up, .. }, _, true) | (Key { code: NumPad8, .. }, _, true) => {
-            player_move_or_attack(0, -1, game, objects);
-            TookTurn
-        }
-        (Key { code: Down, .. }, _, true) | (Key { code: NumPad2, .. }, _, true) => {
-            player_move_or_attack(0, 1, game, objects);
-            TookTurn
-        }
-        (Key { code: Left, .. }, _, true) | (Key { code: NumPad4, .. }, _, true) => {
-            player_move_or_attack(-1, 0, game, objects);
-            TookTurn
-        }
-        (Key { code: Right, .. }, _, true) | (Key { code: NumPad6, .. }, _, true) => {
-            player_move_or_attack(1, 0, game, objects);
-            TookTurn
-        }
-        // diagonals
-        (Key { code: NumPad7, .. }, _, true) => {
-            player_move_or_attack(-1, -1, game, objects);
-            TookTurn
-        }
-        (Key { code: NumPad9, .. }, _, true) => {
-            player_move_or_attack(1, -1, game, objects);
-            TookTurn
-        }
-        (Key { code: NumPad1, .. }, _, true) => {
-            player_move_or_attack(-1, 1, game, objects);
-            TookTurn
-        }
-        (Key { code: NumPad3, .. }, _, true) => {
-            player_move_or_attack(1, 1, game, objects);
-            TookTurn
+            DidntTakeTurn
         }
 
-        // etc
-        _ => return DidntTakeTurn,
+        // do nothing i. e. wait for the monster to come to you
+        (Command::Wait, true) => TookTurn(ACTION_COST),
+
+        // movement, in any direction but the intended one when confused
+        (command, true) => match command.delta() {
+            Some((dx, dy)) => {
+                let confused = objects[game.player]
+                    .fighter
+                    .as_ref()
+                    .is_some_and(|f| f.has_status(StatusKind::Confusion));
+                let (dx, dy) = if confused {
+                    // standing still would be a free attack on yourself
+                    loop {
+                        let step = (game.rng.gen_range(-1, 2), game.rng.gen_range(-1, 2));
+                        if step != (0, 0) {
+                            break step;
+                        }
+                    }
+                } else {
+                    (dx, dy)
+                };
+                TookTurn(player_move_or_attack(dx, dy, game, objects))
+            }
+            None => DidntTakeTurn,
+        },
+
+        // the dead can only look around
+        (_, false) => DidntTakeTurn,
     }
 }
 
@@ -625,21 +907,16 @@ fn initialize_fov(tcod: &mut Tcod, map: &Map) {
     tcod.con.clear();
 }
 
-fn save_game(game: &Game, objects: &[Object]) -> Result<(), Box<dyn Error>> {
-    let save_data = serde_json::to_string(&(game, objects))?;
-    let mut file = File::create(SAVEGAME_FILE)?;
-    file.write_all(save_data.as_bytes())?;
-    Ok(())
-}
-fn load_game() -> Result<(Game, Vec<Object>), Box<dyn Error>> {
-    let mut json_save_state = String::new();
-    let mut file = File::open(SAVEGAME_FILE)?;
-    file.read_to_string(&mut json_save_state)?;
-    let result = serde_json::from_str::<(Game, Vec<Object>)>(&json_save_state)?;
-    Ok(result)
-}
-
-fn new_game(tcod: &mut Tcod) -> (Game, Vec<Object>) {
+/// start a game on dungeon level `depth`, which is 1 unless a test run wants
+/// to skip ahead
+pub fn new_game(
+    tcod: &mut Tcod,
+    seed: Option<u64>,
+    depth: u32,
+    slot: &str,
+    save_format: SaveFormat,
+    permadeath: bool,
+) -> (Game, Objects) {
     // game objects
     let mut player = Object::new(0, 0, '@', WHITE, "player", true);
     player.alive = true;
@@ -648,30 +925,54 @@ fn new_game(tcod: &mut Tcod) -> (Game, Vec<Object>) {
         hp: 100,
         base_defense: 1,
         base_power: 2,
+        damage: FISTS,
+        base_accuracy: 0,
+        base_evasion: 0,
         xp: 0,
         on_death: DeathCallback::Player,
+        attack_cost: ACTION_COST,
+        statuses: vec![],
+        on_hit: None,
+        resistances: vec![],
+        loot: None,
     });
 
-    let mut objects = vec![player];
+    let mut objects = Objects::new();
+    let player = objects.insert(player);
+
+    // every random decision of this run is derived from the seed, which drivers already know
+    let seed = seed.unwrap_or_else(GameRng::random_seed);
+    if tcod.driver.is_none() {
+        println!("Dungeon seed: {}", seed);
+    }
+    let mut rng = GameRng::from_seed(seed);
 
     // game map + message log
+    let map = make_map(&mut objects, player, depth, &mut rng);
+    let identities = Identities::new(&data::get().items, &mut rng);
     let mut game = Game {
-        map: make_map(&mut objects, 1),
+        map,
         messages: Messages::new(),
-        inventory: vec![],
-        dungeon_level: 1,
+        player,
+        dungeon_level: depth,
+        levels: BTreeMap::new(),
+        seed,
+        rng,
+        slot: slot.into(),
+        save_format,
+        permadeath,
+        replay: Some(Replay::new(seed, depth)),
+        turns: 0,
+        killed_by: None,
+        identities,
     };
 
-    let mut dagger = Object::new(0, 0, '-', SKY, "dagger", false);
-    dagger.item = Some(Item::Sword);
-    dagger.equipment = Some(Equipment {
-        equipped: true,
-        slot: Slot::RightHand,
-        max_hp_bonus: 0,
-        defense_bonus: 0,
-        power_bonus: 2,
-    });
-    game.inventory.push(dagger);
+    // start with a dagger already in hand
+    let mut dagger = Item::create("dagger", 0, 0);
+    if let Some(equipment) = dagger.equipment.as_mut() {
+        equipment.equipped = true;
+    }
+    objects[player].inventory.push(dagger);
 
     initialize_fov(tcod, &game.map);
 
@@ -679,21 +980,26 @@ fn new_game(tcod: &mut Tcod) -> (Game, Vec<Object>) {
         "Welcome stranger! Prepare to perish in the Tombs of the Ancient Kings.",
         RED,
     );
+    game.messages
+        .add(format!("Dungeon seed: {}", game.seed), LIGHT_GREY);
 
     (game, objects)
 }
 
-fn play_game(tcod: &mut Tcod, game: &mut Game, objects: &mut Vec<Object>) {
+pub fn play_game(tcod: &mut Tcod, game: &mut Game, objects: &mut Objects) {
     // game loop
     let mut previous_player_position = (-1, -1);
-    while !tcod.root.window_closed() {
-        if objects[PLAYER].pos() != previous_player_position {
-            vision_update(tcod, &mut game.map, &objects[PLAYER]);
+    let mut previous_sight = -1;
+    while !tcod.closed() {
+        let player = &objects[game.player];
+        if player.pos() != previous_player_position || sight_radius(player) != previous_sight {
+            previous_sight = sight_radius(player);
+            vision_update(tcod, &mut game.map, player);
         }
 
-        match input::check_for_event(input::MOUSE | input::KEY_PRESS) {
-            Some((_, Event::Mouse(m))) => tcod.mouse = m,
-            Some((_, Event::Key(k))) => tcod.key = k,
+        match tcod.backend.check_for_event() {
+            Some(Event::Mouse(m)) => tcod.mouse = m,
+            Some(Event::Key(k)) => tcod.key = k,
             _ => tcod.key = Default::default(),
         }
 
@@ -704,63 +1010,269 @@ fn play_game(tcod: &mut Tcod, game: &mut Game, objects: &mut Vec<Object>) {
 
         tcod.con.clear();
         render_all(tcod, game, &objects);
-        tcod.root.flush();
+        tcod.backend.flush();
 
         level_up(tcod, game, objects);
 
-        previous_player_position = objects[PLAYER].pos();
-        let action = handle_keys(tcod, game, objects);
+        previous_player_position = objects[game.player].pos();
+
+        // a stunned player's turns pass without asking for a command
+        let stunned = objects[game.player]
+            .fighter
+            .as_ref()
+            .is_some_and(|f| f.has_status(StatusKind::Stun));
+        if stunned && objects[game.player].alive {
+            objects[game.player].energy -= ACTION_COST;
+            wait_for_player(tcod, game, objects);
+            continue;
+        }
+
+        let command = match tcod.driver.as_mut() {
+            Some(driver) => {
+                let command = driver.command(game, objects);
+                if command.is_none() {
+                    // the driver is done, the keyboard takes over at normal speed
+                    tcod.driver = None;
+                    tcod.backend.set_fps(LIMIT_FPS);
+                }
+                command
+            }
+            None => handle_keys(tcod),
+        };
+        let action = match command {
+            Some(command) => play_command(command, tcod, game, objects),
+            None => PlayerAction::DidntTakeTurn,
+        };
         if action == PlayerAction::Exit {
-            save_game(game, objects).unwrap();
+            // games played by a driver are not saved
+            if tcod.driver.is_none() {
+                if let Err(err) = save_replay(game) {
+                    eprintln!("Could not write the replay: {}", err);
+                }
+                // in permadeath mode the dead stay dead
+                if objects[game.player].alive || !game.permadeath {
+                    save_game(game, objects).unwrap();
+                }
+            }
             break;
         }
-        if action != PlayerAction::DidntTakeTurn && objects[PLAYER].alive {
+        if let PlayerAction::TookTurn(cost) = action {
+            if objects[game.player].alive {
+                objects[game.player].energy -= cost;
+                wait_for_player(tcod, game, objects);
+            }
+        }
+    }
+}
+
+/// Let time pass until the player can act again. Every turn everybody gains
+/// energy at their own speed, and whoever isn't short of energy acts and pays
+/// for it, so fast monsters get several actions in before the player's next one.
+fn wait_for_player(tcod: &mut Tcod, game: &mut Game, objects: &mut Objects) {
+    loop {
+        for id in objects.ids() {
             // only if object is not player
-            for id in 1..objects.len() {
-                if objects[id].ai.is_some() {
-                    ai_take_turn(id, tcod, game, objects)
-                }
+            if id == game.player {
+                continue;
+            }
+            while objects[game.player].alive
+                && objects
+                    .get(id)
+                    .is_some_and(|o| o.ai.is_some() && o.energy >= 0)
+            {
+                let cost = ai_take_turn(id, tcod, game, objects);
+                objects[id].energy -= cost;
+            }
+        }
+
+        let player = &objects[game.player];
+        if player.energy >= 0 || !player.alive {
+            return;
+        }
+
+        // a turn passes
+        game.turns += 1;
+        for id in objects.ids() {
+            if objects.get(id).is_some_and(|o| o.fighter.is_some()) {
+                tick_statuses(id, game, objects);
+                let object = &mut objects[id];
+                object.energy += object.speed();
             }
         }
     }
 }
 
-fn next_level(tcod: &mut Tcod, game: &mut Game, objects: &mut Vec<Object>) {
+fn next_level(tcod: &mut Tcod, game: &mut Game, objects: &mut Objects) {
+    let depth = game.dungeon_level + 1;
+    if game.levels.contains_key(&depth) {
+        game.messages
+            .add(format!("You descend back to level {}.", depth), RED);
+        change_level(depth, tcod, game, objects);
+        return;
+    }
+
     game.messages.add(
         "You take a moment to rest, and recover your strength.",
         VIOLET,
     );
-    let heal_hp = objects[PLAYER].max_hp(game) / 2;
-    objects[PLAYER].heal(heal_hp, game);
+    let heal_hp = objects[game.player].max_hp() / 2;
+    objects[game.player].heal(heal_hp);
 
     game.messages.add(
         "After a rare moment of peace, you descend deeper into \
         the heart of the dungeon...",
         RED,
     );
-    game.dungeon_level += 1;
-    game.map = make_map(objects, game.dungeon_level);
+    change_level(depth, tcod, game, objects);
+}
+
+fn previous_level(tcod: &mut Tcod, game: &mut Game, objects: &mut Objects) {
+    let depth = game.dungeon_level - 1;
+    game.messages
+        .add(format!("You climb back up to level {}.", depth), VIOLET);
+    change_level(depth, tcod, game, objects);
+}
+
+/// put the current level away and move the player to `depth`, generating it
+/// on the first visit
+fn change_level(depth: u32, tcod: &mut Tcod, game: &mut Game, objects: &mut Objects) {
+    // the player travels along, everything else stays with its level
+    let player = objects.remove(game.player).unwrap();
+    let level = Level {
+        map: std::mem::take(&mut game.map),
+        objects: std::mem::take(objects),
+    };
+    game.levels.insert(game.dungeon_level, level);
+
+    let going_down = depth > game.dungeon_level;
+    game.dungeon_level = depth;
+    match game.levels.remove(&depth) {
+        Some(level) => {
+            game.map = level.map;
+            *objects = level.objects;
+            game.player = objects.insert(player);
+
+            // arrive on the stairs leading back to where we came from
+            let arrival = if going_down { UP_STAIRS } else { DOWN_STAIRS };
+            let stairs = objects
+                .iter()
+                .find(|(_, o)| o.name == arrival)
+                .map(|(_, o)| o.pos());
+            if let Some((x, y)) = stairs {
+                objects[game.player].set_pos(x, y);
+            }
+        }
+        None => {
+            game.player = objects.insert(player);
+            game.map = make_map(objects, game.player, depth, &mut game.rng);
+        }
+    }
+
+    initialize_fov(tcod, &game.map);
+    vision_update(tcod, &mut game.map, &objects[game.player]);
+
+    // autosave, so that nothing before the stairs can get lost
+    if tcod.driver.is_none() {
+        if let Err(err) = save_game(game, objects) {
+            game.messages.add(format!("Autosave failed: {}", err), RED);
+        }
+        // the recording too, it's what shows how a crashed game got there
+        if let Err(err) = save_replay(game) {
+            game.messages
+                .add(format!("Could not write the replay: {}", err), RED);
+        }
+    }
+}
+
+/// write what was recorded of the game so far next to its save slot
+fn save_replay(game: &Game) -> Result<(), Box<dyn Error>> {
+    match game.replay.as_ref() {
+        Some(replay) => replay.save(&Replay::path(&game.slot)),
+        None => Ok(()),
+    }
+}
+
+/// Play a recorded game again from its seed, as fast as possible. Once the
+/// recording runs out the keyboard takes over, and the game is saved as `REPLAY_SLOT`.
+pub fn play_replay(tcod: &mut Tcod, replay: Replay) -> (Game, Objects) {
+    let (seed, depth) = (replay.seed, replay.depth);
+    tcod.driver = Some(Box::new(Playback::new(replay)));
+    let (mut game, mut objects) = new_game(
+        tcod,
+        Some(seed),
+        depth,
+        REPLAY_SLOT,
+        SaveFormat::default(),
+        false,
+    );
+    tcod.backend.set_fps(0);
+    play_game(tcod, &mut game, &mut objects);
+    (game, objects)
+}
+
+/// Let the bot play a game of its own from `seed` until it dies or gives up.
+/// Nothing of it is saved.
+pub fn play_bot(tcod: &mut Tcod, seed: u64) -> (Game, Objects) {
+    tcod.driver = Some(Box::new(Bot::new()));
+    let (mut game, mut objects) =
+        new_game(tcod, Some(seed), 1, BOT_SLOT, SaveFormat::default(), false);
+    tcod.backend.set_fps(0);
+    play_game(tcod, &mut game, &mut objects);
+    tcod.driver = None;
+    (game, objects)
+}
+
+/// load the game saved in `slot`, ready to be played
+pub fn load_slot(tcod: &mut Tcod, slot: &str) -> Result<(Game, Objects), LoadError> {
+    let (game, objects) = load_game(slot)?;
     initialize_fov(tcod, &game.map);
+    Ok((game, objects))
+}
+
+/// tell the player why a game couldn't be loaded
+pub fn load_error_box(err: &LoadError, tcod: &mut Tcod) {
+    let width = if let LoadError::Missing = err { 24 } else { 50 };
+    msgbox(&format!("\n{}\n", err), width, tcod);
 }
 
-pub fn main_menu(tcod: &mut Tcod) {
-    let img = tcod::image::Image::from_file("menu_background.png")
-        .ok()
-        .expect("Background image not found");
+/// ask for the save slot and the mode of a new game, `None` if the player backs out
+fn new_game_menu(tcod: &mut Tcod) -> Option<(String, bool)> {
+    let slot = text_input(
+        "Name your save slot:",
+        30,
+        MAX_SLOT_NAME,
+        is_slot_char,
+        tcod,
+    )?;
+    if slot_exists(&slot) {
+        let header = format!("There already is a game saved as '{}'.\n", slot);
+        if menu(&header, &["Overwrite it", "Cancel"], 30, tcod) != Some(0) {
+            return None;
+        }
+    }
+
+    let modes = &[
+        "Normal: keep the save when you die",
+        "Permadeath: delete the save when you die",
+    ];
+    let permadeath = menu("Choose a mode:\n", modes, 44, tcod)? == 1;
+    Some((slot, permadeath))
+}
 
-    while !tcod.root.window_closed() {
-        // show the background image, at twice the regular console resolution
-        tcod::image::blit_2x(&img, (0, 0), (-1, -1), &mut tcod.root, (0, 0));
+pub fn main_menu(tcod: &mut Tcod, seed: Option<u64>, save_format: SaveFormat) {
+    while !tcod.backend.window_closed() {
+        tcod.backend.draw_menu_background();
 
-        tcod.root.set_default_foreground(LIGHT_YELLOW);
-        tcod.root.print_ex(
+        let root = tcod.backend.root();
+        root.set_default_foreground(LIGHT_YELLOW);
+        root.print_ex(
             SCREEN_WIDTH / 2,
             SCREEN_HEIGHT / 2 - 4,
             BackgroundFlag::None,
             TextAlignment::Center,
             "CHASM OF THE UNDERWORLD",
         );
-        tcod.root.print_ex(
+        root.print_ex(
             SCREEN_WIDTH / 2,
             SCREEN_HEIGHT - 2,
             BackgroundFlag::None,
@@ -768,26 +1280,33 @@ pub fn main_menu(tcod: &mut Tcod) {
             "made by babysitterd",
         );
 
-        let choices = &["Play a new game", "Continue last game", "Quit"];
+        let choices = &["Play a new game", "Continue a saved game", "Quit"];
         let choice = menu("", choices, 24, tcod);
 
         match choice {
             Some(0) => {
                 // new game
-                let (mut game, mut objects) = new_game(tcod);
-                play_game(tcod, &mut game, &mut objects);
+                if let Some((slot, permadeath)) = new_game_menu(tcod) {
+                    let (mut game, mut objects) =
+                        new_game(tcod, seed, 1, &slot, save_format, permadeath);
+                    play_game(tcod, &mut game, &mut objects);
+                }
             }
             Some(1) => {
                 // load game
-                match load_game() {
-                    Ok((mut game, mut objects)) => {
-                        initialize_fov(tcod, &game.map);
-                        play_game(tcod, &mut game, &mut objects);
-                    }
-                    Err(_) => {
-                        msgbox("\nNo saved game to load.\n", 24, tcod);
-                        continue;
-                    }
+                let mut slots = list_slots();
+                if slots.is_empty() {
+                    msgbox("\nNo saved game to load.\n", 24, tcod);
+                    continue;
+                }
+                slots.truncate(26);
+                let slot = match menu("Choose a saved game:\n", &slots, 30, tcod) {
+                    Some(index) => &slots[index],
+                    None => continue,
+                };
+                match load_slot(tcod, slot) {
+                    Ok((mut game, mut objects)) => play_game(tcod, &mut game, &mut objects),
+                    Err(err) => load_error_box(&err, tcod),
                 }
             }
             Some(2) => {
@@ -798,3 +1317,82 @@ pub fn main_menu(tcod: &mut Tcod) {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::headless::{HeadlessBackend, MemoryConsole};
+
+    use std::sync::Once;
+
+    /// the data files can only be loaded once per process, whichever test comes first
+    fn load_data() {
+        static LOAD: Once = Once::new();
+        LOAD.call_once(|| data::load(data::DATA_DIR).unwrap());
+    }
+
+    fn headless_tcod() -> Tcod {
+        let backend = HeadlessBackend::new(SCREEN_WIDTH, SCREEN_HEIGHT);
+        Tcod::new(Box::new(backend), Keymap::default())
+    }
+
+    #[test]
+    fn short_seeded_game_draws_the_player() {
+        load_data();
+        let mut tcod = headless_tcod();
+        let mut replay = Replay::new(7, 1);
+        for _ in 0..5 {
+            replay.inputs.push(Input::Command(Command::Wait));
+        }
+        let (game, objects) = play_replay(&mut tcod, replay);
+
+        assert_eq!(game.turns, 5);
+        let (x, y) = objects[game.player].pos();
+        let screen = tcod
+            .backend
+            .root()
+            .as_any()
+            .downcast_ref::<MemoryConsole>()
+            .unwrap();
+        assert_eq!(screen.cell(x, y).unwrap().glyph, '@');
+    }
+
+    #[test]
+    fn replay_plays_on_past_a_quit() {
+        load_data();
+        let mut tcod = headless_tcod();
+        let mut replay = Replay::new(7, 1);
+        for command in [Command::Wait, Command::Exit, Command::Wait] {
+            replay.inputs.push(Input::Command(command));
+        }
+        let (game, _) = play_replay(&mut tcod, replay);
+
+        assert_eq!(game.turns, 2);
+        assert!(!game
+            .replay
+            .unwrap()
+            .inputs
+            .contains(&Input::Command(Command::Exit)));
+    }
+
+    #[test]
+    fn replay_ends_where_the_recorded_game_did() {
+        load_data();
+        let mut tcod = headless_tcod();
+        let (game, objects) = play_bot(&mut tcod, 13);
+        let replay = game.replay.clone().unwrap();
+        let (replayed, replayed_objects) = play_replay(&mut headless_tcod(), replay);
+
+        let hp = |objects: &Objects, player| objects[player].fighter.as_ref().map(|f| f.hp);
+        assert_eq!(replayed.dungeon_level, game.dungeon_level);
+        assert_eq!(replayed.turns, game.turns);
+        assert_eq!(
+            replayed_objects[replayed.player].pos(),
+            objects[game.player].pos()
+        );
+        assert_eq!(
+            hp(&replayed_objects, replayed.player),
+            hp(&objects, game.player)
+        );
+    }
+}
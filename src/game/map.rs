@@ -1,6 +1,8 @@
-use crate::game::PLAYER;
+use crate::data;
+use crate::game::rng::GameRng;
 use crate::object::item::Item;
 use crate::object::monster::Monster;
+use crate::object::store::{Entity, Objects};
 use crate::object::Object;
 
 use tcod::colors::*;
@@ -22,6 +24,17 @@ const MAX_ROOMS: i32 = 30;
 
 pub type Map = Vec<Vec<Tile>>;
 
+pub const DOWN_STAIRS: &str = "down stairs";
+pub const UP_STAIRS: &str = "up stairs";
+
+/// A level the player is not on right now, kept exactly as it was left.
+#[derive(Serialize, Deserialize)]
+pub struct Level {
+    pub map: Map,
+    /// everything on the level except for the player
+    pub objects: Objects,
+}
+
 /// A tile of the map and its properties
 #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct Tile {
@@ -34,28 +47,30 @@ pub fn is_out_of_bounds(x: i32, y: i32) -> bool {
     x < 0 || x >= MAP_WIDTH || y < 0 || y >= MAP_HEIGHT
 }
 
-pub fn is_blocked(x: i32, y: i32, map: &Map, objects: &[Object]) -> bool {
+pub fn is_blocked(x: i32, y: i32, map: &Map, objects: &Objects) -> bool {
     if map[x as usize][y as usize].blocked {
         return true;
     }
 
     objects
         .iter()
-        .any(|object| object.pos() == (x, y) && object.blocks)
+        .any(|(_, object)| object.pos() == (x, y) && object.blocks)
 }
 
-pub fn make_map(objects: &mut Vec<Object>, level: u32) -> Map {
+/// generate a new level into `objects`, which should hold nothing but the player,
+/// and put the player in the first room
+pub fn make_map(objects: &mut Objects, player: Entity, level: u32, rng: &mut GameRng) -> Map {
     let mut map = vec![vec![Tile::wall(); MAP_HEIGHT as usize]; MAP_WIDTH as usize];
 
     let mut rooms: Vec<Rectangle> = vec![];
 
     for _ in 0..MAX_ROOMS {
         // random width and height
-        let w = rand::thread_rng().gen_range(ROOM_MIN_SIZE, ROOM_MAX_SIZE + 1);
-        let h = rand::thread_rng().gen_range(ROOM_MIN_SIZE, ROOM_MAX_SIZE + 1);
+        let w = rng.gen_range(ROOM_MIN_SIZE, ROOM_MAX_SIZE + 1);
+        let h = rng.gen_range(ROOM_MIN_SIZE, ROOM_MAX_SIZE + 1);
         // random position without going out of the boundaries of the map
-        let x = rand::thread_rng().gen_range(0, MAP_WIDTH - w);
-        let y = rand::thread_rng().gen_range(0, MAP_HEIGHT - h);
+        let x = rng.gen_range(0, MAP_WIDTH - w);
+        let y = rng.gen_range(0, MAP_HEIGHT - h);
 
         let new_room = Rectangle::new(x, y, w, h);
 
@@ -67,7 +82,7 @@ pub fn make_map(objects: &mut Vec<Object>, level: u32) -> Map {
             if let Some(prev) = rooms.last() {
                 let (prev_x, prev_y) = prev.center();
 
-                if rand::random() {
+                if rng.gen() {
                     carve_h_tunnel(prev_x, new_x, prev_y, &mut map);
                     carve_v_tunnel(prev_y, new_y, new_x, &mut map);
                 } else {
@@ -75,27 +90,31 @@ pub fn make_map(objects: &mut Vec<Object>, level: u32) -> Map {
                     carve_h_tunnel(prev_x, new_x, new_y, &mut map);
                 }
             } else {
-                let player = &mut objects[PLAYER];
-                player.set_pos(new_x, new_y);
+                objects[player].set_pos(new_x, new_y);
             }
 
             rooms.push(Rectangle::new(x, y, w, h));
         }
     }
 
-    // fresh start: clean up all everything except for the player
-    objects.truncate(1);
-
     for room in &rooms {
         carve_room(room, &mut map);
-        fill_with_objects(&room, &map, objects, level);
+        fill_with_objects(&room, &map, objects, level, rng);
     }
 
     // stairs to go one level deeper
     let last_room = rooms[rooms.len() - 1].center();
-    let mut stairs = Object::new(last_room.0, last_room.1, '<', WHITE, "stairs", false);
+    let mut stairs = Object::new(last_room.0, last_room.1, '<', WHITE, DOWN_STAIRS, false);
     stairs.always_visible = true;
-    objects.push(stairs);
+    objects.insert(stairs);
+
+    // and back up where the player arrives, except on the first level
+    if level > 1 {
+        let first_room = rooms[0].center();
+        let mut stairs = Object::new(first_room.0, first_room.1, '>', WHITE, UP_STAIRS, false);
+        stairs.always_visible = true;
+        objects.insert(stairs);
+    }
 
     map
 }
@@ -168,12 +187,87 @@ impl Rectangle {
     }
 }
 
-struct Transition {
-    level: u32,
-    value: u32,
+/// A value that changes with depth: it applies from `level` on, until the
+/// next transition in the table takes over.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Transition {
+    pub level: u32,
+    pub value: u32,
+}
+
+/// How likely a monster or item (by template id) is to spawn, per depth.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SpawnChance {
+    pub id: String,
+    pub weight: Vec<Transition>,
+}
+
+/// What the dungeon generator puts into rooms, as described in the spawns data file.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SpawnTable {
+    pub max_monsters: Vec<Transition>,
+    pub max_items: Vec<Transition>,
+    pub monsters: Vec<SpawnChance>,
+    pub items: Vec<SpawnChance>,
+}
+
+impl SpawnTable {
+    /// make sure every table is usable: levels in increasing order, ids that
+    /// exist, and something to pick from whenever a room may get a spawn
+    pub fn validate(&self, monster_ids: &[&str], item_ids: &[&str]) -> Result<(), String> {
+        check_order("max_monsters", &self.max_monsters)?;
+        check_order("max_items", &self.max_items)?;
+        check_chances("monster", &self.monsters, monster_ids)?;
+        check_chances("item", &self.items, item_ids)?;
+
+        // tables only change at transitions, so checking those levels covers all depths
+        let mut levels = vec![1];
+        levels.extend(self.max_monsters.iter().map(|t| t.level));
+        levels.extend(self.max_items.iter().map(|t| t.level));
+        for chance in self.monsters.iter().chain(self.items.iter()) {
+            levels.extend(chance.weight.iter().map(|t| t.level));
+        }
+        for level in levels {
+            if from_dungeon_level(&self.max_monsters, level) > 0
+                && total_weight(&self.monsters, level) == 0
+            {
+                return Err(format!("No monster can spawn on level {}", level));
+            }
+            if from_dungeon_level(&self.max_items, level) > 0
+                && total_weight(&self.items, level) == 0
+            {
+                return Err(format!("No item can spawn on level {}", level));
+            }
+        }
+        Ok(())
+    }
+}
+
+pub fn check_order(name: &str, table: &[Transition]) -> Result<(), String> {
+    if table.windows(2).any(|pair| pair[0].level >= pair[1].level) {
+        return Err(format!("Levels in '{}' must be increasing", name));
+    }
+    Ok(())
+}
+
+pub fn check_chances(kind: &str, chances: &[SpawnChance], ids: &[&str]) -> Result<(), String> {
+    for chance in chances {
+        if !ids.contains(&chance.id.as_str()) {
+            return Err(format!("Unknown {} '{}' in spawn table", kind, chance.id));
+        }
+        check_order(&chance.id, &chance.weight)?;
+    }
+    Ok(())
 }
 
-fn from_dungeon_level(table: &[Transition], level: u32) -> u32 {
+fn total_weight(chances: &[SpawnChance], level: u32) -> u32 {
+    chances
+        .iter()
+        .map(|chance| from_dungeon_level(&chance.weight, level))
+        .sum()
+}
+
+pub fn from_dungeon_level(table: &[Transition], level: u32) -> u32 {
     table
         .iter()
         .rev()
@@ -181,130 +275,61 @@ fn from_dungeon_level(table: &[Transition], level: u32) -> u32 {
         .map_or(0, |transition| transition.value)
 }
 
-fn fill_with_objects(room: &Rectangle, map: &Map, objects: &mut Vec<Object>, level: u32) {
-    let max_monsters = from_dungeon_level(
-        &[
-            Transition { level: 1, value: 2 },
-            Transition { level: 4, value: 3 },
-            Transition { level: 6, value: 5 },
-        ],
-        level,
-    );
-
-    let troll_chance = from_dungeon_level(
-        &[
-            Transition {
-                level: 3,
-                value: 15,
-            },
-            Transition {
-                level: 5,
-                value: 30,
-            },
-            Transition {
-                level: 7,
-                value: 60,
-            },
-        ],
-        level,
-    );
-
-    let mut monster_chances = [
-        Weighted {
-            weight: 80,
-            item: Monster::Orc,
-        },
-        Weighted {
-            weight: troll_chance,
-            item: Monster::Troll,
-        },
-    ];
-    let monster_choice = WeightedChoice::new(&mut monster_chances);
-
-    let num_monsters = rand::thread_rng().gen_range(0, max_monsters + 1);
-    for _ in 0..num_monsters {
-        let x = rand::thread_rng().gen_range(room.x1 + 1, room.x2);
-        let y = rand::thread_rng().gen_range(room.y1 + 1, room.y2);
-
-        if is_blocked(x, y, map, objects) {
-            continue;
-        }
+pub fn weighted_ids(chances: &[SpawnChance], level: u32) -> Vec<Weighted<&str>> {
+    chances
+        .iter()
+        .map(|chance| Weighted {
+            weight: from_dungeon_level(&chance.weight, level),
+            item: chance.id.as_str(),
+        })
+        .collect()
+}
 
-        let monster = Monster::create(monster_choice.ind_sample(&mut rand::thread_rng()), x, y);
-        objects.push(monster);
-    }
+fn fill_with_objects(
+    room: &Rectangle,
+    map: &Map,
+    objects: &mut Objects,
+    level: u32,
+    rng: &mut GameRng,
+) {
+    let spawns = &data::get().spawns;
+
+    let max_monsters = from_dungeon_level(&spawns.max_monsters, level);
+    let num_monsters = rng.gen_range(0, max_monsters + 1);
+    if num_monsters > 0 {
+        let mut monster_chances = weighted_ids(&spawns.monsters, level);
+        let monster_choice = WeightedChoice::new(&mut monster_chances);
+
+        for _ in 0..num_monsters {
+            let x = rng.gen_range(room.x1 + 1, room.x2);
+            let y = rng.gen_range(room.y1 + 1, room.y2);
+
+            if is_blocked(x, y, map, objects) {
+                continue;
+            }
 
-    let max_items = from_dungeon_level(
-        &[
-            Transition { level: 1, value: 1 },
-            Transition { level: 4, value: 2 },
-        ],
-        level,
-    );
-
-    let mut item_chances = [
-        Weighted {
-            weight: 35,
-            item: Item::Heal,
-        },
-        Weighted {
-            weight: from_dungeon_level(&[Transition { level: 4, value: 5 }], level),
-            item: Item::Sword,
-        },
-        Weighted {
-            weight: from_dungeon_level(
-                &[Transition {
-                    level: 8,
-                    value: 15,
-                }],
-                level,
-            ),
-            item: Item::Shield,
-        },
-        Weighted {
-            weight: from_dungeon_level(
-                &[Transition {
-                    level: 4,
-                    value: 25,
-                }],
-                level,
-            ),
-            item: Item::Lightning,
-        },
-        Weighted {
-            weight: from_dungeon_level(
-                &[Transition {
-                    level: 6,
-                    value: 25,
-                }],
-                level,
-            ),
-            item: Item::Fireball,
-        },
-        Weighted {
-            weight: from_dungeon_level(
-                &[Transition {
-                    level: 2,
-                    value: 10,
-                }],
-                level,
-            ),
-            item: Item::Confusion,
-        },
-    ];
-    let item_choice = WeightedChoice::new(&mut item_chances);
-
-    let num_items = rand::thread_rng().gen_range(0, max_items + 1);
-    for _ in 0..num_items {
-        let x = rand::thread_rng().gen_range(room.x1 + 1, room.x2);
-        let y = rand::thread_rng().gen_range(room.y1 + 1, room.y2);
-
-        if is_blocked(x, y, map, objects) {
-            continue;
+            let monster = Monster::create(monster_choice.ind_sample(rng), x, y);
+            objects.insert(monster);
         }
+    }
 
-        let mut item = Item::create(item_choice.ind_sample(&mut rand::thread_rng()), x, y);
-        item.always_visible = true;
-        objects.push(item);
+    let max_items = from_dungeon_level(&spawns.max_items, level);
+    let num_items = rng.gen_range(0, max_items + 1);
+    if num_items > 0 {
+        let mut item_chances = weighted_ids(&spawns.items, level);
+        let item_choice = WeightedChoice::new(&mut item_chances);
+
+        for _ in 0..num_items {
+            let x = rng.gen_range(room.x1 + 1, room.x2);
+            let y = rng.gen_range(room.y1 + 1, room.y2);
+
+            if is_blocked(x, y, map, objects) {
+                continue;
+            }
+
+            let mut item = Item::create(item_choice.ind_sample(rng), x, y);
+            item.always_visible = true;
+            objects.insert(item);
+        }
     }
 }
@@ -0,0 +1,201 @@
+use crate::game::map::{is_out_of_bounds, Map};
+use crate::object::store::Objects;
+
+use std::cmp::{self, Ordering};
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+/// A tile waiting to be expanded, ordered so the heap pops the most promising one.
+#[derive(PartialEq, Eq)]
+struct Node {
+    estimate: i32,
+    cost: i32,
+    pos: (i32, i32),
+}
+
+impl Ord for Node {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // BinaryHeap is a max-heap: lower estimates first, then the ones closer to the goal
+        other
+            .estimate
+            .cmp(&self.estimate)
+            .then_with(|| self.cost.cmp(&other.cost))
+            .then_with(|| self.pos.cmp(&other.pos))
+    }
+}
+
+impl PartialOrd for Node {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// number of steps between two tiles when diagonal moves are allowed
+fn distance((x1, y1): (i32, i32), (x2, y2): (i32, i32)) -> i32 {
+    cmp::max((x1 - x2).abs(), (y1 - y2).abs())
+}
+
+/// Find the shortest path from `from` to `to` with A*, moving in all 8 directions.
+/// Walls and blocking objects are avoided, except on `to` itself so a monster can
+/// path right up to whoever it's chasing. Paths longer than `max_length` steps are
+/// not worth following and give `None`, just like unreachable targets.
+/// The returned path starts with the first step and ends on `to`.
+pub fn find_path(
+    map: &Map,
+    objects: &Objects,
+    from: (i32, i32),
+    to: (i32, i32),
+    max_length: i32,
+) -> Option<Vec<(i32, i32)>> {
+    let occupied: HashSet<(i32, i32)> = objects
+        .iter()
+        .filter(|(_, o)| o.blocks)
+        .map(|(_, o)| o.pos())
+        .collect();
+    let passable = |(x, y): (i32, i32)| {
+        (x, y) == to
+            || (!is_out_of_bounds(x, y)
+                && !map[x as usize][y as usize].blocked
+                && !occupied.contains(&(x, y)))
+    };
+
+    let mut open = BinaryHeap::new();
+    let mut came_from: HashMap<(i32, i32), (i32, i32)> = HashMap::new();
+    let mut best_cost: HashMap<(i32, i32), i32> = HashMap::new();
+
+    best_cost.insert(from, 0);
+    open.push(Node {
+        estimate: distance(from, to),
+        cost: 0,
+        pos: from,
+    });
+
+    while let Some(Node { cost, pos, .. }) = open.pop() {
+        if pos == to {
+            let mut path = vec![pos];
+            let mut current = pos;
+            while let Some(&previous) = came_from.get(&current) {
+                if previous == from {
+                    break;
+                }
+                path.push(previous);
+                current = previous;
+            }
+            path.reverse();
+            return Some(path);
+        }
+
+        // a cheaper way here was already expanded
+        if best_cost.get(&pos).is_some_and(|&best| cost > best) {
+            continue;
+        }
+
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                let next = (pos.0 + dx, pos.1 + dy);
+                if (dx, dy) == (0, 0) || !passable(next) {
+                    continue;
+                }
+
+                let next_cost = cost + 1;
+                let estimate = next_cost + distance(next, to);
+                if estimate > max_length {
+                    continue;
+                }
+                if best_cost.get(&next).is_none_or(|&best| next_cost < best) {
+                    best_cost.insert(next, next_cost);
+                    came_from.insert(next, pos);
+                    open.push(Node {
+                        estimate,
+                        cost: next_cost,
+                        pos: next,
+                    });
+                }
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::map::{Tile, MAP_HEIGHT, MAP_WIDTH};
+    use crate::object::Object;
+
+    use tcod::colors::WHITE;
+
+    fn open_map() -> Map {
+        vec![vec![Tile::empty(); MAP_HEIGHT as usize]; MAP_WIDTH as usize]
+    }
+
+    fn orc(x: i32, y: i32) -> Object {
+        Object::new(x, y, 'o', WHITE, "orc", true)
+    }
+
+    /// every step goes to a neighbouring tile, starting next to `from`
+    fn assert_connected(from: (i32, i32), path: &[(i32, i32)]) {
+        let mut previous = from;
+        for &step in path {
+            assert_eq!(distance(previous, step), 1, "{:?} -> {:?}", previous, step);
+            previous = step;
+        }
+    }
+
+    #[test]
+    fn walks_around_walls() {
+        let mut map = open_map();
+        for tile in map[5].iter_mut().take(11) {
+            *tile = Tile::wall();
+        }
+        let path = find_path(&map, &Objects::new(), (2, 2), (8, 2), 100).unwrap();
+
+        assert_connected((2, 2), &path);
+        assert_eq!(path.last(), Some(&(8, 2)));
+        assert!(path
+            .iter()
+            .all(|&(x, y)| !map[x as usize][y as usize].blocked));
+        // down past the end of the wall at (5, 11) and back up
+        assert_eq!(path.len(), 18);
+    }
+
+    #[test]
+    fn walks_around_blocking_objects_but_up_to_the_target() {
+        let mut objects = Objects::new();
+        objects.insert(orc(4, 2));
+        objects.insert(orc(6, 2));
+        let path = find_path(&open_map(), &objects, (2, 2), (6, 2), 100).unwrap();
+
+        assert_connected((2, 2), &path);
+        assert!(!path.contains(&(4, 2)));
+        assert_eq!(path.last(), Some(&(6, 2)));
+        assert_eq!(path.len(), 4);
+    }
+
+    #[test]
+    fn walled_in_target_is_unreachable() {
+        let mut map = open_map();
+        for x in 9..=11 {
+            for y in 9..=11 {
+                if (x, y) != (10, 10) {
+                    map[x as usize][y as usize] = Tile::wall();
+                }
+            }
+        }
+        assert_eq!(
+            find_path(&map, &Objects::new(), (2, 2), (10, 10), 100),
+            None
+        );
+    }
+
+    #[test]
+    fn paths_longer_than_the_limit_are_not_found() {
+        let map = open_map();
+        let objects = Objects::new();
+        assert_eq!(find_path(&map, &objects, (2, 2), (12, 2), 9), None);
+        assert_eq!(
+            find_path(&map, &objects, (2, 2), (12, 2), 10).map(|p| p.len()),
+            Some(10)
+        );
+    }
+}
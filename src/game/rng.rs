@@ -0,0 +1,42 @@
+use rand::{Rng, SeedableRng};
+
+use serde::{Deserialize, Serialize};
+
+/// Game-owned random number generator (SplitMix64).
+/// Its whole state is a single integer, so it can be saved along with the game
+/// and the same seed always produces the same sequence.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GameRng {
+    state: u64,
+}
+
+impl GameRng {
+    /// pick a fresh seed when the player didn't supply one
+    pub fn random_seed() -> u64 {
+        rand::thread_rng().gen()
+    }
+}
+
+impl Rng for GameRng {
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+impl SeedableRng<u64> for GameRng {
+    fn reseed(&mut self, seed: u64) {
+        self.state = seed;
+    }
+
+    fn from_seed(seed: u64) -> Self {
+        GameRng { state: seed }
+    }
+}
@@ -0,0 +1,733 @@
+use crate::game::rng::GameRng;
+use crate::game::Game;
+use crate::object::store::Objects;
+
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::PathBuf;
+
+use rand::SeedableRng;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// every save slot is a file in here, named after the slot
+const SAVE_DIR: &str = "saves";
+
+/// where the game was saved before there were slots
+const LEGACY_SAVE_FILE: &str = "savegame.dat";
+
+/// the slot a save from before slots is moved to
+pub const LEGACY_SLOT: &str = "savegame";
+
+/// longest slot name the player can type
+pub const MAX_SLOT_NAME: usize = 20;
+
+/// version of the game that wrote a save, for the error message when it's too new
+const GAME_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// first bytes of every binary save
+const BINARY_MAGIC: &[u8; 4] = b"COTU";
+
+/// Upgrades a save from one format version to the next, on the raw JSON.
+type Migration = fn(Value) -> Result<Value, String>;
+
+/// `MIGRATIONS[n]` turns a format `n` save into format `n + 1`,
+/// so a save of any older format can be brought up to date one step at a time.
+/// Add a migration here whenever `Game` or `Object` change shape.
+/// Binary saves are not self-describing and can't be migrated, only JSON ones.
+const MIGRATIONS: &[Migration] = &[
+    from_headerless,
+    add_permadeath,
+    add_replay,
+    add_replay_depth,
+    add_turns,
+    add_statuses,
+    add_energy,
+    add_ranged,
+    add_dice,
+    add_resistances,
+    add_two_handed,
+    move_inventory,
+    add_loot,
+    add_stacks,
+    add_identities,
+];
+
+/// the format version saves are written in
+pub const FORMAT_VERSION: u32 = MIGRATIONS.len() as u32;
+
+/// How a save is encoded on disk.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum SaveFormat {
+    /// small, with a checksum against truncated or tampered files,
+    /// but only readable by the format version that wrote it
+    Binary,
+    /// plain readable JSON, which older saves can be upgraded from
+    #[default]
+    Json,
+}
+
+impl SaveFormat {
+    const ALL: [SaveFormat; 2] = [SaveFormat::Binary, SaveFormat::Json];
+
+    fn extension(self) -> &'static str {
+        match self {
+            SaveFormat::Binary => "sav",
+            SaveFormat::Json => "json",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Header {
+    pub format: u32,
+    pub game_version: String,
+}
+
+#[derive(Serialize)]
+struct SaveRef<'a> {
+    header: Header,
+    game: &'a Game,
+    objects: &'a Objects,
+}
+
+/// what's left once the header has been checked
+#[derive(Deserialize)]
+struct SaveFile {
+    game: Game,
+    objects: Objects,
+}
+
+#[derive(Debug)]
+pub enum LoadError {
+    /// there is no save to load
+    Missing,
+    /// the save can't be read or doesn't make sense
+    Corrupt(String),
+    /// the save was written by a newer version of the game
+    TooNew { format: u32, game_version: String },
+    /// a binary save from an older version, only JSON saves can be upgraded
+    Outdated { format: u32, game_version: String },
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::Missing => write!(f, "No saved game to load."),
+            LoadError::Corrupt(reason) => write!(f, "The saved game is corrupt: {}", reason),
+            LoadError::TooNew {
+                format,
+                game_version,
+            } => write!(
+                f,
+                "The saved game comes from a newer version of the game \
+                 ({}, save format {}) and can't be loaded.",
+                game_version, format
+            ),
+            LoadError::Outdated {
+                format,
+                game_version,
+            } => write!(
+                f,
+                "The saved game comes from an older version of the game \
+                 ({}, save format {}) and binary saves can't be upgraded.",
+                game_version, format
+            ),
+        }
+    }
+}
+
+impl Error for LoadError {}
+
+fn corrupt<E: ToString>(error: E) -> LoadError {
+    LoadError::Corrupt(error.to_string())
+}
+
+fn slot_path(slot: &str, format: SaveFormat) -> PathBuf {
+    PathBuf::from(SAVE_DIR)
+        .join(slot)
+        .with_extension(format.extension())
+}
+
+/// characters allowed in slot names, so that every name makes a valid file name
+pub fn is_slot_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == ' '
+}
+
+pub fn slot_exists(slot: &str) -> bool {
+    SaveFormat::ALL
+        .iter()
+        .any(|&format| slot_path(slot, format).is_file())
+}
+
+/// names of all save slots, sorted
+pub fn list_slots() -> Vec<String> {
+    let entries = match fs::read_dir(SAVE_DIR) {
+        Ok(entries) => entries,
+        Err(_) => return vec![],
+    };
+    let mut slots: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension().is_some_and(|ext| {
+                SaveFormat::ALL
+                    .iter()
+                    .any(|format| ext == format.extension())
+            })
+        })
+        .filter_map(|path| {
+            path.file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+        })
+        .collect();
+    slots.sort();
+    slots.dedup();
+    slots
+}
+
+/// Move the single save of the versions before slots into `LEGACY_SLOT`, so it
+/// shows up with the others. Older saves are JSON, they get upgraded on load.
+/// Returns whether there was one to move.
+pub fn import_legacy_save() -> io::Result<bool> {
+    if !PathBuf::from(LEGACY_SAVE_FILE).is_file() || slot_exists(LEGACY_SLOT) {
+        return Ok(false);
+    }
+    fs::create_dir_all(SAVE_DIR)?;
+    fs::rename(LEGACY_SAVE_FILE, slot_path(LEGACY_SLOT, SaveFormat::Json))?;
+    Ok(true)
+}
+
+/// remove a slot's save, if it has been saved at all
+pub fn delete_slot(slot: &str) -> io::Result<()> {
+    for format in SaveFormat::ALL {
+        match fs::remove_file(slot_path(slot, format)) {
+            Err(ref e) if e.kind() == ErrorKind::NotFound => {}
+            result => result?,
+        }
+    }
+    Ok(())
+}
+
+/// write the game to its save slot, in the game's save format
+pub fn save_game(game: &Game, objects: &Objects) -> Result<(), Box<dyn Error>> {
+    let save = SaveRef {
+        header: Header {
+            format: FORMAT_VERSION,
+            game_version: GAME_VERSION.into(),
+        },
+        game,
+        objects,
+    };
+    let save_data = match game.save_format {
+        SaveFormat::Binary => encode_binary(&save)?,
+        SaveFormat::Json => serde_json::to_vec(&save)?,
+    };
+
+    // write a copy first, so a crash halfway through leaves the old save intact
+    fs::create_dir_all(SAVE_DIR)?;
+    let path = slot_path(&game.slot, game.save_format);
+    let temp_path = path.with_extension("tmp");
+    fs::write(&temp_path, save_data)?;
+    fs::rename(&temp_path, &path)?;
+
+    // a slot holds a single save, don't leave one in the other format behind
+    for format in SaveFormat::ALL {
+        if format != game.save_format {
+            match fs::remove_file(slot_path(&game.slot, format)) {
+                Err(ref e) if e.kind() == ErrorKind::NotFound => {}
+                result => result?,
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn load_game(slot: &str) -> Result<(Game, Objects), LoadError> {
+    let format = SaveFormat::ALL
+        .into_iter()
+        .find(|&format| slot_path(slot, format).is_file())
+        .ok_or(LoadError::Missing)?;
+    let save_data = fs::read(slot_path(slot, format)).map_err(corrupt)?;
+
+    let mut save = match format {
+        SaveFormat::Binary => decode_binary(&save_data)?,
+        SaveFormat::Json => decode_json(&save_data)?,
+    };
+    save.game.slot = slot.into();
+    save.game.save_format = format;
+    Ok((save.game, save.objects))
+}
+
+/// The binary layout is the magic bytes, the bincode encoded save and a
+/// CRC32 of everything before it.
+fn encode_binary(save: &SaveRef) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut data = BINARY_MAGIC.to_vec();
+    bincode::serialize_into(&mut data, save)?;
+    let checksum = crc32fast::hash(&data);
+    data.extend_from_slice(&checksum.to_le_bytes());
+    Ok(data)
+}
+
+fn decode_binary(data: &[u8]) -> Result<SaveFile, LoadError> {
+    if data.len() < BINARY_MAGIC.len() + 4 || !data.starts_with(BINARY_MAGIC) {
+        return Err(corrupt("not a save file"));
+    }
+    let (data, checksum) = data.split_at(data.len() - 4);
+    if crc32fast::hash(data).to_le_bytes() != checksum {
+        return Err(corrupt(
+            "checksum mismatch, the file is truncated or was modified",
+        ));
+    }
+    let payload = &data[BINARY_MAGIC.len()..];
+
+    // the header comes first, check it before trying to read the rest
+    let header: Header = bincode::deserialize(payload).map_err(corrupt)?;
+    if header.format > FORMAT_VERSION {
+        return Err(LoadError::TooNew {
+            format: header.format,
+            game_version: header.game_version,
+        });
+    }
+    if header.format < FORMAT_VERSION {
+        return Err(LoadError::Outdated {
+            format: header.format,
+            game_version: header.game_version,
+        });
+    }
+
+    let (_, game, objects): (Header, Game, Objects) =
+        bincode::deserialize(payload).map_err(corrupt)?;
+    Ok(SaveFile { game, objects })
+}
+
+fn decode_json(data: &[u8]) -> Result<SaveFile, LoadError> {
+    let mut save: Value = serde_json::from_slice(data).map_err(corrupt)?;
+
+    let format = json_format(&save)?;
+    if format > FORMAT_VERSION {
+        let game_version = save["header"]["game_version"]
+            .as_str()
+            .unwrap_or("unknown")
+            .to_string();
+        return Err(LoadError::TooNew {
+            format,
+            game_version,
+        });
+    }
+    for migration in &MIGRATIONS[format as usize..] {
+        save = migration(save).map_err(LoadError::Corrupt)?;
+    }
+
+    serde_json::from_value(save).map_err(corrupt)
+}
+
+fn json_format(save: &Value) -> Result<u32, LoadError> {
+    // the very first saves were a bare (game, objects) pair
+    if save.is_array() {
+        return Ok(0);
+    }
+    save["header"]["format"]
+        .as_u64()
+        .map(|format| format as u32)
+        .ok_or_else(|| corrupt("no save header"))
+}
+
+/// Format 0 -> 1: wrap the bare (game, objects) pair in a header, and catch up
+/// with everything that changed before saves had a version:
+/// objects moved into an entity store, items became template ids and the
+/// dungeon got a seed and visited levels.
+fn from_headerless(save: Value) -> Result<Value, String> {
+    let (mut game, mut objects) = match save {
+        Value::Array(mut pair) if pair.len() == 2 => {
+            let objects = pair.pop().unwrap();
+            (pair.pop().unwrap(), objects)
+        }
+        _ => return Err("expected a (game, objects) pair".into()),
+    };
+
+    let fields = game.as_object_mut().ok_or("game is not an object")?;
+    if !fields.contains_key("seed") {
+        // there is no telling what the old dungeon was made from, the rest of it gets a new seed
+        let seed = GameRng::random_seed();
+        fields.insert("seed".into(), json!(seed));
+        fields.insert(
+            "rng".into(),
+            serde_json::to_value(GameRng::from_seed(seed)).map_err(|e| e.to_string())?,
+        );
+    }
+    fields.entry("levels").or_insert(json!({}));
+
+    if let Some(inventory) = game["inventory"].as_array_mut() {
+        inventory.iter_mut().for_each(upgrade_item);
+    }
+    if let Some(levels) = game["levels"].as_object_mut() {
+        for level in levels.values_mut() {
+            upgrade_objects(&mut level["objects"]);
+        }
+    }
+
+    // the player always used to be the first object
+    if objects.is_array() {
+        game["player"] = json!({ "index": 0, "generation": 0 });
+    }
+    upgrade_objects(&mut objects);
+
+    Ok(json!({
+        "header": { "format": 1, "game_version": "0.1.0" },
+        "game": game,
+        "objects": objects,
+    }))
+}
+
+/// turn a plain list of objects into an entity store, keeping their order
+fn upgrade_objects(objects: &mut Value) {
+    if let Some(list) = objects.as_array_mut() {
+        let slots: Vec<Value> = list
+            .drain(..)
+            .map(|mut object| {
+                upgrade_item(&mut object);
+                json!({ "generation": 0, "object": object })
+            })
+            .collect();
+        *objects = json!({ "slots": slots, "free": [] });
+    }
+}
+
+/// items used to be a bare enum, turn it into the template id and the effect
+/// every item of that kind had
+fn upgrade_item(object: &mut Value) {
+    let (id, effect) = match object["item"].as_str() {
+        Some("Heal") => ("healing_potion", json!({ "Heal": { "amount": 40 } })),
+        Some("Lightning") => (
+            "lightning_scroll",
+            json!({ "Lightning": { "damage": 40, "range": 5 } }),
+        ),
+        Some("Confusion") => (
+            "confusion_scroll",
+            json!({ "Confusion": { "range": 8, "turns": 10 } }),
+        ),
+        Some("Fireball") => (
+            "fireball_scroll",
+            json!({ "Fireball": { "damage": 25, "radius": 3 } }),
+        ),
+        Some("Shield") => ("shield", Value::Null),
+        Some(_) => ("sword", Value::Null),
+        None => return,
+    };
+    object["item"] = json!({ "id": id, "effect": effect });
+}
+
+/// Format 1 -> 2: games can be played in permadeath mode, older ones weren't.
+fn add_permadeath(mut save: Value) -> Result<Value, String> {
+    let game = save["game"]
+        .as_object_mut()
+        .ok_or("game is not an object")?;
+    game.entry("permadeath").or_insert(json!(false));
+    save["header"]["format"] = json!(2);
+    Ok(save)
+}
+
+/// Format 2 -> 3: games are recorded for replays, but older ones are missing
+/// their beginning and can't be replayed.
+fn add_replay(mut save: Value) -> Result<Value, String> {
+    let game = save["game"]
+        .as_object_mut()
+        .ok_or("game is not an object")?;
+    game.entry("replay").or_insert(Value::Null);
+    save["header"]["format"] = json!(3);
+    Ok(save)
+}
+
+/// Format 3 -> 4: games can start deeper than level 1, and replays remember where.
+fn add_replay_depth(mut save: Value) -> Result<Value, String> {
+    if let Some(replay) = save["game"]["replay"].as_object_mut() {
+        replay.entry("depth").or_insert(json!(1));
+    }
+    save["header"]["format"] = json!(4);
+    Ok(save)
+}
+
+/// Format 4 -> 5: games count their turns and remember what killed the player.
+/// Turns before that weren't counted.
+fn add_turns(mut save: Value) -> Result<Value, String> {
+    let game = save["game"]
+        .as_object_mut()
+        .ok_or("game is not an object")?;
+    game.entry("turns").or_insert(json!(0));
+    game.entry("killed_by").or_insert(Value::Null);
+    save["header"]["format"] = json!(5);
+    Ok(save)
+}
+
+/// Format 5 -> 6: fighters carry status effects, and confusion moved out of
+/// the monster's ai into them.
+fn add_statuses(mut save: Value) -> Result<Value, String> {
+    upgrade_fighters(&mut save["objects"]);
+    if let Some(levels) = save["game"]["levels"].as_object_mut() {
+        for level in levels.values_mut() {
+            upgrade_fighters(&mut level["objects"]);
+        }
+    }
+    save["header"]["format"] = json!(6);
+    Ok(save)
+}
+
+fn upgrade_fighters(objects: &mut Value) {
+    let slots = match objects["slots"].as_array_mut() {
+        Some(slots) => slots,
+        None => return,
+    };
+    // free slots hold null, which indexing would turn into an object
+    for object in slots
+        .iter_mut()
+        .map(|slot| &mut slot["object"])
+        .filter(|o| o.is_object())
+    {
+        let fighter = match object["fighter"].as_object_mut() {
+            Some(fighter) => fighter,
+            None => continue,
+        };
+        fighter.entry("statuses").or_insert(json!([]));
+        fighter.entry("on_hit").or_insert(Value::Null);
+
+        // a confused ai kept the one to go back to, and counted down to -1
+        if let Some(confused) = object["ai"].get("Confused").cloned() {
+            let mut ai = confused["previous_ai"].clone();
+            while let Some(previous) = ai.get("Confused") {
+                ai = previous["previous_ai"].clone();
+            }
+            let turns = confused["lasts_for"].as_i64().unwrap_or(0) + 1;
+            object["ai"] = ai;
+            object["fighter"]["statuses"] = json!([
+                { "kind": "Confusion", "turns": turns, "potency": 0 }
+            ]);
+        }
+    }
+}
+
+/// Format 6 -> 7: objects act on energy gained at their own speed, and attacks
+/// have a cost. Everything used to be as fast as the player.
+fn add_energy(mut save: Value) -> Result<Value, String> {
+    for_all_objects(&mut save, add_object_energy);
+    save["header"]["format"] = json!(7);
+    Ok(save)
+}
+
+fn add_object_energy(object: &mut Value) {
+    let fields = match object.as_object_mut() {
+        Some(fields) => fields,
+        None => return,
+    };
+    fields.entry("base_speed").or_insert(json!(100));
+    fields.entry("energy").or_insert(json!(0));
+    if let Some(fighter) = object["fighter"].as_object_mut() {
+        fighter.entry("attack_cost").or_insert(json!(100));
+    }
+}
+
+/// Format 7 -> 8: equipment can be a bow or crossbow and items can be ammunition,
+/// nothing was either before.
+fn add_ranged(mut save: Value) -> Result<Value, String> {
+    for_all_objects(&mut save, |object| {
+        if let Some(equipment) = object["equipment"].as_object_mut() {
+            equipment.entry("launcher").or_insert(Value::Null);
+        }
+        if let Some(item) = object["item"].as_object_mut() {
+            item.entry("ammo").or_insert(Value::Null);
+        }
+    });
+    save["header"]["format"] = json!(8);
+    Ok(save)
+}
+
+/// Format 8 -> 9: attacks roll to hit and roll their damage. Older fighters
+/// and weapons get no dice and no accuracy, so their hits still come down to
+/// power against defense, and the player fights with bare fists.
+fn add_dice(mut save: Value) -> Result<Value, String> {
+    for_all_objects(&mut save, |object| {
+        if let Some(fighter) = object["fighter"].as_object_mut() {
+            let damage = if fighter.get("on_death") == Some(&json!("Player")) {
+                "1d3"
+            } else {
+                "0d0"
+            };
+            fighter.entry("damage").or_insert(json!(damage));
+            fighter.entry("base_accuracy").or_insert(json!(0));
+            fighter.entry("base_evasion").or_insert(json!(0));
+        }
+        if let Some(equipment) = object["equipment"].as_object_mut() {
+            equipment.entry("damage").or_insert(Value::Null);
+            equipment.entry("accuracy_bonus").or_insert(json!(0));
+            equipment.entry("evasion_bonus").or_insert(json!(0));
+            if let Some(launcher) = equipment
+                .get_mut("launcher")
+                .and_then(|l| l.as_object_mut())
+            {
+                launcher.entry("damage").or_insert(json!("0d0"));
+            }
+        }
+    });
+    save["header"]["format"] = json!(9);
+    Ok(save)
+}
+
+/// Format 9 -> 10: damage has a kind, and fighters and equipment can resist
+/// some kinds. Nothing resisted anything before.
+fn add_resistances(mut save: Value) -> Result<Value, String> {
+    for_all_objects(&mut save, |object| {
+        if let Some(fighter) = object["fighter"].as_object_mut() {
+            fighter.entry("resistances").or_insert(json!([]));
+        }
+        if let Some(equipment) = object["equipment"].as_object_mut() {
+            equipment.entry("resistance").or_insert(Value::Null);
+        }
+    });
+    save["header"]["format"] = json!(10);
+    Ok(save)
+}
+
+/// Format 10 -> 11: weapons can take both hands, none used to.
+fn add_two_handed(mut save: Value) -> Result<Value, String> {
+    for_all_objects(&mut save, |object| {
+        if let Some(equipment) = object["equipment"].as_object_mut() {
+            equipment.entry("two_handed").or_insert(json!(false));
+        }
+    });
+    save["header"]["format"] = json!(11);
+    Ok(save)
+}
+
+/// Format 11 -> 12: every object can carry an inventory, the player's moved
+/// out of the game into the player object.
+fn move_inventory(mut save: Value) -> Result<Value, String> {
+    for_all_objects(&mut save, |object| {
+        if let Some(fields) = object.as_object_mut() {
+            fields.entry("inventory").or_insert(json!([]));
+        }
+    });
+    let inventory = save["game"]
+        .as_object_mut()
+        .and_then(|game| game.remove("inventory"))
+        .unwrap_or(json!([]));
+    let player = save["game"]["player"]["index"]
+        .as_u64()
+        .ok_or("the game has no player")?;
+    let slot = save["objects"]["slots"]
+        .get_mut(player as usize)
+        .filter(|slot| slot["object"].is_object())
+        .ok_or("the player is missing from the objects")?;
+    slot["object"]["inventory"] = inventory;
+    save["header"]["format"] = json!(12);
+    Ok(save)
+}
+
+/// Format 12 -> 13: objects carry gold, and monsters roll a loot table when
+/// they die. Monsters from before have none and only drop what they carry.
+fn add_loot(mut save: Value) -> Result<Value, String> {
+    for_all_objects(&mut save, |object| {
+        if let Some(fields) = object.as_object_mut() {
+            fields.entry("gold").or_insert(json!(0));
+        }
+        if let Some(fighter) = object["fighter"].as_object_mut() {
+            fighter.entry("loot").or_insert(Value::Null);
+        }
+    });
+    save["header"]["format"] = json!(13);
+    Ok(save)
+}
+
+/// Format 13 -> 14: items come in stacks with a count. Ammunition moves its
+/// count to the stack and is named for a single arrow or bolt, and identical
+/// potions and scrolls carried separately go onto one stack.
+fn add_stacks(mut save: Value) -> Result<Value, String> {
+    for_all_objects(&mut save, |object| {
+        if let Some(item) = object["item"].as_object_mut() {
+            let ammo = item.get_mut("ammo").and_then(|ammo| ammo.as_object_mut());
+            let name = match ammo.as_ref().and_then(|ammo| ammo.get("kind")) {
+                Some(kind) if kind == "Bolt" => Some("crossbow bolt"),
+                Some(_) => Some("arrow"),
+                None => None,
+            };
+            let count = ammo.and_then(|ammo| ammo.remove("count"));
+            item.entry("count").or_insert(count.unwrap_or(json!(1)));
+            if let Some(name) = name {
+                object["name"] = json!(name);
+            }
+        }
+    });
+    for_all_objects(&mut save, |object| {
+        let inventory = match object["inventory"].as_array_mut() {
+            Some(inventory) => inventory,
+            None => return,
+        };
+        let mut stacks: Vec<Value> = vec![];
+        for item in inventory.drain(..) {
+            let stack = stacks.iter_mut().find(|stack| {
+                stack["equipment"].is_null()
+                    && item["equipment"].is_null()
+                    && stack["item"]["id"] == item["item"]["id"]
+            });
+            match stack {
+                Some(stack) => {
+                    let count = stack["item"]["count"].as_i64().unwrap_or(1)
+                        + item["item"]["count"].as_i64().unwrap_or(1);
+                    stack["item"]["count"] = json!(count);
+                }
+                None => stacks.push(item),
+            }
+        }
+        *inventory = stacks;
+    });
+    save["header"]["format"] = json!(14);
+    Ok(save)
+}
+
+/// Format 14 -> 15: potions and scrolls start out unidentified. Games from
+/// before keep knowing every item by its true name.
+fn add_identities(mut save: Value) -> Result<Value, String> {
+    save["game"]["identities"] = json!({ "appearances": {}, "known": [] });
+    save["header"]["format"] = json!(15);
+    Ok(save)
+}
+
+/// run `upgrade` on the objects of the current level, the stored levels and
+/// the inventories, including what objects carry
+fn for_all_objects(save: &mut Value, upgrade: fn(&mut Value)) {
+    for_each_object(&mut save["objects"], upgrade);
+    if let Some(levels) = save["game"]["levels"].as_object_mut() {
+        for level in levels.values_mut() {
+            for_each_object(&mut level["objects"], upgrade);
+        }
+    }
+    if let Some(inventory) = save["game"]["inventory"].as_array_mut() {
+        inventory
+            .iter_mut()
+            .for_each(|object| with_carried(object, upgrade));
+    }
+}
+
+/// run `upgrade` on every object of an entity store, skipping its free slots
+fn for_each_object(objects: &mut Value, upgrade: fn(&mut Value)) {
+    if let Some(slots) = objects["slots"].as_array_mut() {
+        slots
+            .iter_mut()
+            .map(|slot| &mut slot["object"])
+            .filter(|object| object.is_object())
+            .for_each(|object| with_carried(object, upgrade));
+    }
+}
+
+/// run `upgrade` on `object` and everything in its inventory
+fn with_carried(object: &mut Value, upgrade: fn(&mut Value)) {
+    upgrade(object);
+    // indexing would add a null inventory to objects from before they had one
+    if let Some(inventory) = object.get_mut("inventory").and_then(|i| i.as_array_mut()) {
+        inventory
+            .iter_mut()
+            .for_each(|item| with_carried(item, upgrade));
+    }
+}
@@ -1,23 +1,124 @@
+pub mod backend;
+pub mod cli;
+pub mod command;
 pub mod config;
+pub mod data;
 pub mod game;
 pub mod object;
 
+use backend::headless::HeadlessBackend;
+use backend::window::TcodBackend;
+use backend::Backend;
+use cli::Start;
+use command::bot::autoplay;
+use command::keymap::{Keymap, KEYMAP_FILE};
+use command::replay::Replay;
 use config::{LIMIT_FPS, SCREEN_HEIGHT, SCREEN_WIDTH};
-use game::{main_menu, Tcod};
+use game::rng::GameRng;
+use game::save::{import_legacy_save, slot_exists, LEGACY_SLOT};
+use game::{load_error_box, load_slot, main_menu, new_game, play_game, play_replay, Tcod};
 
 use tcod::console::*;
 
+use std::process;
+
 fn main() {
-    tcod::system::set_fps(LIMIT_FPS);
+    let options = cli::parse(std::env::args().skip(1)).unwrap_or_else(|err| {
+        eprintln!("{}, see --help for the options", err);
+        process::exit(2);
+    });
+    if options.help {
+        print!("{}", cli::USAGE);
+        return;
+    }
+    match import_legacy_save() {
+        Ok(true) => println!("Moved the old saved game to the '{}' slot", LEGACY_SLOT),
+        Ok(false) => {}
+        Err(err) => eprintln!("Could not move the old saved game to a slot: {}", err),
+    }
+    if let Start::New { slot, .. } = &options.start {
+        if slot_exists(slot) {
+            eprintln!(
+                "There already is a game saved as '{}', continue it with --load",
+                slot
+            );
+            process::exit(1);
+        }
+    }
+    let replay = match &options.start {
+        Start::Replay(path) => Some(Replay::load(path).unwrap_or_else(|err| {
+            eprintln!("Could not load the replay: {}", err);
+            process::exit(1);
+        })),
+        _ => None,
+    };
+
+    if let Err(err) = data::load(data::DATA_DIR) {
+        eprintln!("Could not load game data: {}", err);
+        process::exit(1);
+    }
 
-    let root = Root::initializer()
-        .font("arial10x10.png", FontLayout::Tcod)
-        .font_type(FontType::Greyscale)
-        .size(SCREEN_WIDTH, SCREEN_HEIGHT)
-        .title("Rust/libtcod tutorial")
-        .init();
+    let keymap = Keymap::load(KEYMAP_FILE).unwrap_or_else(|err| {
+        eprintln!(
+            "Could not read {}, using the default keys: {}",
+            KEYMAP_FILE, err
+        );
+        Keymap::default()
+    });
 
-    let mut tcod = Tcod::new(root);
+    let backend: Box<dyn Backend> = if options.headless {
+        Box::new(HeadlessBackend::new(SCREEN_WIDTH, SCREEN_HEIGHT))
+    } else {
+        tcod::system::set_fps(LIMIT_FPS);
+        let root = Root::initializer()
+            .font(&options.font, options.font_layout)
+            .font_type(FontType::Greyscale)
+            .size(SCREEN_WIDTH, SCREEN_HEIGHT)
+            .title("Rust/libtcod tutorial")
+            .fullscreen(options.fullscreen)
+            .init();
+        Box::new(TcodBackend::new(root))
+    };
+    let mut tcod = Tcod::new(backend, keymap);
 
-    main_menu(&mut tcod);
+    match options.start {
+        Start::Menu => main_menu(&mut tcod, options.seed, options.save_format),
+        Start::New {
+            slot,
+            depth,
+            permadeath,
+        } => {
+            let (mut game, mut objects) = new_game(
+                &mut tcod,
+                options.seed,
+                depth,
+                &slot,
+                options.save_format,
+                permadeath,
+            );
+            play_game(&mut tcod, &mut game, &mut objects);
+        }
+        Start::Load(slot) => match load_slot(&mut tcod, &slot) {
+            Ok((mut game, mut objects)) => play_game(&mut tcod, &mut game, &mut objects),
+            Err(err) => {
+                // show what went wrong, then let the player pick another game
+                load_error_box(&err, &mut tcod);
+                main_menu(&mut tcod, options.seed, options.save_format);
+            }
+        },
+        Start::Autoplay(games) => {
+            let first_seed = options.seed.unwrap_or_else(GameRng::random_seed);
+            autoplay(&mut tcod, first_seed, games);
+        }
+        Start::Replay(_) => {
+            let (game, objects) = play_replay(&mut tcod, replay.unwrap());
+            if options.headless {
+                println!(
+                    "Replay finished on dungeon level {} with {} HP left",
+                    game.dungeon_level,
+                    objects[game.player].fighter.as_ref().map_or(0, |f| f.hp)
+                );
+            }
+        }
+    }
 }
@@ -1,28 +1,53 @@
 pub mod ai;
+pub mod damage;
+pub mod dice;
 pub mod equipment;
 pub mod fighter;
+pub mod identify;
 pub mod item;
+pub mod loot;
 pub mod monster;
+pub mod ranged;
+pub mod status;
+pub mod store;
 
 use ai::Ai;
+use damage::{resist, DamageKind, MAX_RESISTANCE};
+use dice::Dice;
 use equipment::Equipment;
-use fighter::Fighter;
+use fighter::{DeathCallback, Fighter};
 use item::Item;
+use ranged::Launcher;
+use status::{apply_status, StatusKind};
 
 use crate::game::Game;
 use crate::game::Messages;
 
+use crate::backend::Surface;
+
 use tcod::colors::*;
-use tcod::console::*;
+use tcod::console::BackgroundFlag;
 
 use std::cmp;
 
+use rand::Rng;
+
 use serde::{Deserialize, Serialize};
 
 // experience and level-ups
 const LEVEL_UP_BASE: i32 = 200;
 const LEVEL_UP_FACTOR: i32 = 150;
 
+// to-hit rolls, in percent: accuracy and evasion move the chance within the bounds
+const BASE_HIT_CHANCE: i32 = 80;
+const MIN_HIT_CHANCE: i32 = 5;
+const MAX_HIT_CHANCE: i32 = 95;
+const CRITICAL_CHANCE: i32 = 5;
+
+// energy gained every turn at normal speed, and what an ordinary action costs
+pub const NORMAL_SPEED: i32 = 100;
+pub const ACTION_COST: i32 = 100;
+
 /// This is a generic object: the player, a monster, an item, the stairs...
 /// It's always represented by a character on screen.
 #[derive(Debug, Serialize, Deserialize)]
@@ -40,6 +65,14 @@ pub struct Object {
     pub item: Option<Item>,
     pub equipment: Option<Equipment>,
     pub always_visible: bool,
+    /// items carried, the equipped ones count towards the stats
+    pub inventory: Vec<Object>,
+    /// gold carried, or how much a pile of gold is worth
+    pub gold: i32,
+    /// energy gained every turn, before haste and slowness
+    pub base_speed: i32,
+    /// the object can act while this isn't negative, acting costs energy
+    pub energy: i32,
 }
 
 impl Object {
@@ -58,95 +91,261 @@ impl Object {
             item: None,
             equipment: None,
             always_visible: false,
+            inventory: vec![],
+            gold: 0,
+            base_speed: NORMAL_SPEED,
+            energy: 0,
         }
     }
 
-    pub fn take_damage(&mut self, damage: i32, game: &mut Game) -> Option<i32> {
+    /// take `damage` of the given kind, less whatever the resistances take off,
+    /// and return the experience for the kill if it was one
+    pub fn take_damage(&mut self, damage: i32, kind: DamageKind, game: &mut Game) -> Option<i32> {
+        let damage = self.resisted(damage, kind);
         if let Some(fighter) = self.fighter.as_mut() {
             if damage > 0 {
                 fighter.hp = cmp::max(fighter.hp - damage, 0);
             }
         }
 
-        if let Some(fighter) = self.fighter {
+        if let Some(fighter) = self.fighter.as_ref() {
             if self.alive && fighter.hp == 0 {
+                let (on_death, xp) = (fighter.on_death, fighter.xp);
                 self.alive = false;
-                fighter.on_death.callback(self, game);
-                return Some(fighter.xp);
+                on_death.callback(self, game);
+                return Some(xp);
             }
         }
 
         None
     }
 
-    pub fn attack(&mut self, other: &mut Object, game: &mut Game) {
-        let damage = self.power(game) - other.defense(game);
-        if damage > 0 {
+    /// speed with haste and slowness taken into account
+    pub fn speed(&self) -> i32 {
+        match self.fighter.as_ref() {
+            Some(f) if f.has_status(StatusKind::Haste) => self.base_speed * 2,
+            Some(f) if f.has_status(StatusKind::Slow) => self.base_speed / 2,
+            _ => self.base_speed,
+        }
+    }
+
+    /// energy an attack takes, heavy hitters take longer
+    pub fn attack_cost(&self) -> i32 {
+        self.fighter.as_ref().map_or(ACTION_COST, |f| f.attack_cost)
+    }
+
+    /// attack `other` in melee, `true` if it killed it
+    pub fn attack(&mut self, other: &mut Object, game: &mut Game) -> bool {
+        let (damage, power) = (self.damage(), self.power());
+        self.strike(other, damage, power, "attacks", game)
+    }
+
+    /// hit `other` with a shot from `launcher`, `true` if it killed it
+    pub fn shoot(&mut self, other: &mut Object, launcher: Launcher, game: &mut Game) -> bool {
+        let power = self.power() + launcher.power_bonus;
+        self.strike(other, launcher.damage, power, "shoots", game)
+    }
+
+    /// Roll to hit, then roll `damage` plus `power` minus the other's defense,
+    /// which works as armor. Critical hits roll the damage twice and go
+    /// through armor. `verb` says how the blow is dealt. Returns whether
+    /// `other` died of it.
+    fn strike(
+        &mut self,
+        other: &mut Object,
+        damage: Dice,
+        power: i32,
+        verb: &str,
+        game: &mut Game,
+    ) -> bool {
+        let hit_chance = BASE_HIT_CHANCE + self.accuracy() - other.evasion();
+        let hit_chance = hit_chance.clamp(MIN_HIT_CHANCE, MAX_HIT_CHANCE);
+        if game.rng.gen_range(0, 100) >= hit_chance {
             game.messages.add(
-                format!(
-                    "{} attacks {} for {} hit points.",
-                    self.name, other.name, damage
-                ),
+                format!("{} {} {} but misses.", self.name, verb, other.name),
                 WHITE,
             );
-            if let Some(xp) = other.take_damage(damage, game) {
+            return false;
+        }
+
+        let critical = game.rng.gen_range(0, 100) < CRITICAL_CHANCE;
+        let raw_damage = if critical {
+            damage.roll(&mut game.rng) + damage.roll(&mut game.rng) + power
+        } else {
+            damage.roll(&mut game.rng) + power - other.defense()
+        };
+        let damage = other.resisted(raw_damage, DamageKind::Physical);
+        if damage > 0 {
+            let message = if critical {
+                format!(
+                    "{} {} {} with a critical hit for {} hit points!",
+                    self.name, verb, other.name, damage
+                )
+            } else {
+                format!(
+                    "{} {} {} for {} hit points.",
+                    self.name, verb, other.name, damage
+                )
+            };
+            game.messages
+                .add(message, if critical { YELLOW } else { WHITE });
+            if let Some(xp) = other.take_damage(raw_damage, DamageKind::Physical, game) {
+                if other
+                    .fighter
+                    .as_ref()
+                    .is_some_and(|f| f.on_death == DeathCallback::Player)
+                {
+                    game.killed_by = Some(self.name.clone());
+                }
                 self.fighter.as_mut().unwrap().xp += xp;
+                return true;
+            } else if let Some(status) = self.fighter.as_ref().and_then(|f| f.on_hit) {
+                apply_status(other, status, game);
             }
         } else {
             game.messages.add(
-                format!("{} attacks {} but it has no effect!", self.name, other.name),
+                format!(
+                    "{} {} {} but the blow glances off its armor.",
+                    self.name, verb, other.name
+                ),
                 WHITE,
             );
         }
+        false
+    }
+
+    /// the name with the size of the stack, for items
+    pub fn stack_name(&self) -> String {
+        match self.item.as_ref() {
+            Some(item) => item.stack_name(&self.name),
+            None => self.name.clone(),
+        }
+    }
+
+    /// whether `other` can go onto this item's stack: the same potion,
+    /// scroll or ammunition, never equipment
+    pub fn stacks_with(&self, other: &Object) -> bool {
+        match (self.item.as_ref(), other.item.as_ref()) {
+            (Some(item), Some(other_item)) => {
+                item.id == other_item.id && self.equipment.is_none() && other.equipment.is_none()
+            }
+            _ => false,
+        }
     }
 
-    pub fn heal(&mut self, amount: i32, game: &Game) {
-        let max_hp = self.max_hp(game);
+    pub fn heal(&mut self, amount: i32) {
+        let max_hp = self.max_hp();
         if let Some(ref mut fighter) = self.fighter {
             fighter.hp = cmp::min(fighter.hp + amount, max_hp);
         }
     }
 
-    pub fn max_hp(&self, game: &Game) -> i32 {
-        let base_max_hp = self.fighter.map_or(0, |f| f.base_max_hp);
+    pub fn max_hp(&self) -> i32 {
+        let base_max_hp = self.fighter.as_ref().map_or(0, |f| f.base_max_hp);
+        let bonus: i32 = self.get_all_equipped().iter().map(|e| e.max_hp_bonus).sum();
+        base_max_hp + bonus
+    }
+
+    pub fn power(&self) -> i32 {
+        let base_power = self.fighter.as_ref().map_or(0, |f| f.base_power);
+        let bonus: i32 = self.get_all_equipped().iter().map(|e| e.power_bonus).sum();
+        base_power + bonus
+    }
+
+    pub fn defense(&self) -> i32 {
+        let base_defense = self.fighter.as_ref().map_or(0, |f| f.base_defense);
         let bonus: i32 = self
-            .get_all_equipped(game)
+            .get_all_equipped()
             .iter()
-            .map(|e| e.max_hp_bonus)
+            .map(|e| e.defense_bonus)
             .sum();
-        base_max_hp + bonus
+        base_defense + bonus
+    }
+
+    /// the damage roll of the wielded weapon, or the fighter's own without one
+    pub fn damage(&self) -> Dice {
+        let own = self.fighter.as_ref().map_or(Dice::default(), |f| f.damage);
+        self.get_all_equipped()
+            .iter()
+            .find_map(|e| e.damage)
+            .unwrap_or(own)
     }
 
-    pub fn power(&self, game: &Game) -> i32 {
-        let base_power = self.fighter.map_or(0, |f| f.base_power);
+    pub fn accuracy(&self) -> i32 {
+        let base_accuracy = self.fighter.as_ref().map_or(0, |f| f.base_accuracy);
         let bonus: i32 = self
-            .get_all_equipped(game)
+            .get_all_equipped()
             .iter()
-            .map(|e| e.power_bonus)
+            .map(|e| e.accuracy_bonus)
             .sum();
-        base_power + bonus
+        base_accuracy + bonus
     }
 
-    pub fn defense(&self, game: &Game) -> i32 {
-        let base_defense = self.fighter.map_or(0, |f| f.base_defense);
+    pub fn evasion(&self) -> i32 {
+        let base_evasion = self.fighter.as_ref().map_or(0, |f| f.base_evasion);
         let bonus: i32 = self
-            .get_all_equipped(game)
+            .get_all_equipped()
             .iter()
-            .map(|e| e.defense_bonus)
+            .map(|e| e.evasion_bonus)
             .sum();
-        base_defense + bonus
+        base_evasion + bonus
     }
 
-    pub fn get_all_equipped(&self, game: &Game) -> Vec<Equipment> {
-        if self.name == "player" {
-            game.inventory
+    /// how much of `kind` of damage the fighter and their equipment shrug off, in percent
+    pub fn resistance(&self, kind: DamageKind) -> i32 {
+        let own: i32 = self.fighter.as_ref().map_or(0, |f| {
+            f.resistances
                 .iter()
-                .filter(|item| item.equipment.map_or(false, |e| e.equipped))
-                .map(|item| item.equipment.unwrap())
-                .collect()
-        } else {
-            vec![]
+                .filter(|r| r.kind == kind)
+                .map(|r| r.percent)
+                .sum()
+        });
+        let bonus: i32 = self
+            .get_all_equipped()
+            .iter()
+            .filter_map(|e| e.resistance)
+            .filter(|r| r.kind == kind)
+            .map(|r| r.percent)
+            .sum();
+        (own + bonus).min(MAX_RESISTANCE)
+    }
+
+    /// what is left of `damage` of the given kind after the resistances
+    pub fn resisted(&self, damage: i32, kind: DamageKind) -> i32 {
+        resist(damage, self.resistance(kind))
+    }
+
+    pub fn get_all_equipped(&self) -> Vec<Equipment> {
+        self.inventory
+            .iter()
+            .filter(|item| item.equipment.map_or(false, |e| e.equipped))
+            .map(|item| item.equipment.unwrap())
+            .collect()
+    }
+
+    /// Put on the equipment at `index` of the inventory, or take it off if it's
+    /// on. A ring goes on the free hand, anything else takes the place of
+    /// whatever is in its slots, e.g. a two-handed weapon of the shield.
+    pub fn toggle_equipment(&mut self, index: usize, messages: &mut Messages) {
+        let equipment = match self.inventory[index].equipment {
+            Some(equipment) => equipment,
+            None => return,
+        };
+
+        if equipment.equipped {
+            self.inventory[index].dequip(messages);
+            return;
+        }
+        if let Some(slot) = Equipment::free_slot(equipment, &self.inventory) {
+            self.inventory[index].equipment.as_mut().unwrap().slot = slot;
+        }
+        let equipment = self.inventory[index].equipment.unwrap();
+        for slot in equipment.slots() {
+            if let Some(current) = Equipment::get_equipped_in_slot(slot, &self.inventory) {
+                self.inventory[current].dequip(messages);
+            }
         }
+        self.inventory[index].equip(messages);
     }
 
     pub fn equip(&mut self, messages: &mut Messages) {
@@ -161,7 +360,7 @@ impl Object {
             if !equipment.equipped {
                 equipment.equipped = true;
                 messages.add(
-                    format!("Equipped {} on {}.", self.name, equipment.slot),
+                    format!("Equipped {} on {}.", self.name, equipment.worn_on()),
                     LIGHT_GREEN,
                 );
             }
@@ -185,7 +384,7 @@ impl Object {
             if equipment.equipped {
                 equipment.equipped = false;
                 messages.add(
-                    format!("Dequipped {} on {}.", self.name, equipment.slot),
+                    format!("Dequipped {} on {}.", self.name, equipment.worn_on()),
                     LIGHT_GREEN,
                 );
             }
@@ -202,8 +401,8 @@ impl Object {
     }
 
     /// set the color and then draw the character that represents this object at its position
-    pub fn draw(&self, con: &mut dyn Console) {
-        con.set_default_foreground(self.color);
+    pub fn draw(&self, con: &mut dyn Surface, color: Color) {
+        con.set_default_foreground(color);
         con.put_char(self.x, self.y, self.glyph, BackgroundFlag::None);
     }
 
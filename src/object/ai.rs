@@ -1,81 +1,125 @@
-use crate::config::PLAYER;
-use crate::game::{move_by, Game, Tcod};
-use crate::object::Object;
-
-use tcod::colors::*;
+use crate::game::pathfinding::find_path;
+use crate::game::{move_by, Game, Tcod, TORCH_RADIUS};
+use crate::object::status::StatusKind;
+use crate::object::store::{Entity, Objects};
+use crate::object::ACTION_COST;
 
 use rand::Rng;
 
 use serde::{Deserialize, Serialize};
 
+// monsters don't bother with detours longer than this
+const MAX_PATH_LENGTH: i32 = 25;
+
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum Ai {
     Basic,
-    Confused {
-        previous_ai: Box<Ai>,
-        lasts_for: i32,
-    },
 }
 
-pub fn ai_take_turn(monster_id: usize, tcod: &Tcod, game: &mut Game, objects: &mut [Object]) {
+/// let the monster act, returning the energy it took
+pub fn ai_take_turn(
+    monster_id: Entity,
+    tcod: &Tcod,
+    game: &mut Game,
+    objects: &mut Objects,
+) -> i32 {
     use Ai::*;
-    if let Some(ai) = objects[monster_id].ai.take() {
-        let new_ai = match ai {
-            Basic => ai_basic(monster_id, tcod, game, objects),
-            Confused {
-                previous_ai,
-                lasts_for,
-            } => ai_confused(monster_id, tcod, game, objects, previous_ai, lasts_for),
-        };
-        objects[monster_id].ai = Some(new_ai);
+    let has_status = |kind, objects: &Objects| {
+        objects[monster_id]
+            .fighter
+            .as_ref()
+            .is_some_and(|f| f.has_status(kind))
+    };
+    // stunned monsters lose their turn, confused ones stumble around whatever their ai
+    if has_status(StatusKind::Stun, objects) {
+        return ACTION_COST;
+    }
+    if has_status(StatusKind::Confusion, objects) {
+        stumble(monster_id, game, objects);
+        return ACTION_COST;
+    }
+    match objects[monster_id].ai.take() {
+        Some(ai) => {
+            let (new_ai, cost) = match ai {
+                Basic => ai_basic(monster_id, tcod, game, objects),
+            };
+            objects[monster_id].ai = Some(new_ai);
+            // a free action would let the monster act forever
+            cost.max(1)
+        }
+        None => ACTION_COST,
     }
 }
 
-pub fn ai_basic(monster_id: usize, tcod: &Tcod, game: &mut Game, objects: &mut [Object]) -> Ai {
+pub fn ai_basic(
+    monster_id: Entity,
+    tcod: &Tcod,
+    game: &mut Game,
+    objects: &mut Objects,
+) -> (Ai, i32) {
     let (monster_x, monster_y) = objects[monster_id].pos();
-    if tcod.fov.is_in_fov(monster_x, monster_y) {
-        if objects[monster_id].distance_to(&objects[PLAYER]) >= 2.0 {
-            let (player_x, player_y) = objects[PLAYER].pos();
-            move_towards(monster_id, player_x, player_y, game, objects);
-        } else if objects[PLAYER].fighter.map_or(false, |f| f.hp >= 0) {
-            let (player, monster) = mut_two(PLAYER, monster_id, objects);
+    let distance = objects[monster_id].distance_to(&objects[game.player]);
+    let monster_blind = objects[monster_id]
+        .fighter
+        .as_ref()
+        .is_some_and(|f| f.has_status(StatusKind::Blindness));
+    // a blind player can't hide, monsters close by still come for them
+    let player_blind = objects[game.player]
+        .fighter
+        .as_ref()
+        .is_some_and(|f| f.has_status(StatusKind::Blindness));
+    let sees_player = tcod.fov.is_in_fov(monster_x, monster_y)
+        || (player_blind && distance <= TORCH_RADIUS as f32);
+
+    if sees_player {
+        if distance >= 2.0 {
+            // blind monsters only notice what is right next to them
+            if !monster_blind {
+                let (player_x, player_y) = objects[game.player].pos();
+                move_astar(monster_id, player_x, player_y, game, objects);
+            }
+        } else if objects[game.player]
+            .fighter
+            .as_ref()
+            .is_some_and(|f| f.hp >= 0)
+        {
+            let (monster, player) = objects.get_two_mut(monster_id, game.player);
             monster.attack(player, game);
+            return (Ai::Basic, monster.attack_cost());
         }
     }
-    Ai::Basic
+    (Ai::Basic, ACTION_COST)
 }
 
-pub fn ai_confused(
-    monster_id: usize,
-    _tcod: &Tcod,
-    game: &mut Game,
-    objects: &mut [Object],
-    previous_ai: Box<Ai>,
-    lasts_for: i32,
-) -> Ai {
-    if lasts_for >= 0 {
-        // still confused
-        move_by(
-            monster_id,
-            rand::thread_rng().gen_range(-1, 2),
-            rand::thread_rng().gen_range(-1, 2),
-            game,
-            objects,
-        );
-        Ai::Confused {
-            previous_ai: previous_ai,
-            lasts_for: lasts_for - 1,
-        }
-    } else {
-        game.messages.add(
-            format!("The {} is no longer confused!", objects[monster_id].name),
-            RED,
-        );
-        *previous_ai
+/// move one step in a random direction
+fn stumble(monster_id: Entity, game: &mut Game, objects: &mut Objects) {
+    move_by(
+        monster_id,
+        game.rng.gen_range(-1, 2),
+        game.rng.gen_range(-1, 2),
+        game,
+        objects,
+    );
+}
+
+/// take the first step of the shortest path around walls and other monsters,
+/// or head straight for the target if there is no such path
+fn move_astar(id: Entity, target_x: i32, target_y: i32, game: &mut Game, objects: &mut Objects) {
+    let from = objects[id].pos();
+    let path = find_path(
+        &game.map,
+        objects,
+        from,
+        (target_x, target_y),
+        MAX_PATH_LENGTH,
+    );
+    match path.as_ref().and_then(|path| path.first()) {
+        Some(&(x, y)) => move_by(id, x - from.0, y - from.1, game, objects),
+        None => move_towards(id, target_x, target_y, game, objects),
     }
 }
 
-fn move_towards(id: usize, target_x: i32, target_y: i32, game: &mut Game, objects: &mut [Object]) {
+fn move_towards(id: Entity, target_x: i32, target_y: i32, game: &mut Game, objects: &mut Objects) {
     let dx = target_x - objects[id].x;
     let dy = target_y - objects[id].y;
     let distance = ((dx * dx + dy * dy) as f32).sqrt();
@@ -86,10 +130,3 @@ fn move_towards(id: usize, target_x: i32, target_y: i32, game: &mut Game, object
     let dy = (dy as f32 / distance).round() as i32;
     move_by(id, dx, dy, game, objects);
 }
-
-/// Mutably borrow two *separate* elements from the slice
-fn mut_two<T>(first_id: usize, second_id: usize, items: &mut [T]) -> (&mut T, &mut T) {
-    assert!(first_id < second_id);
-    let (first_slice, second_slice) = items.split_at_mut(second_id);
-    (&mut first_slice[first_id], &mut second_slice[0])
-}
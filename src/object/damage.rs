@@ -0,0 +1,50 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DamageKind {
+    /// weapons, claws and teeth
+    Physical,
+    Fire,
+    Lightning,
+    Poison,
+    Cold,
+}
+
+impl DamageKind {
+    pub const ALL: [DamageKind; 5] = [
+        DamageKind::Physical,
+        DamageKind::Fire,
+        DamageKind::Lightning,
+        DamageKind::Poison,
+        DamageKind::Cold,
+    ];
+}
+
+impl std::fmt::Display for DamageKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        use DamageKind::*;
+        match self {
+            Physical => write!(f, "physical"),
+            Fire => write!(f, "fire"),
+            Lightning => write!(f, "lightning"),
+            Poison => write!(f, "poison"),
+            Cold => write!(f, "cold"),
+        }
+    }
+}
+
+/// How much of one kind of damage a fighter shrugs off, in percent: 100 is
+/// immune, a negative one is a vulnerability that makes it hurt more.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Resistance {
+    pub kind: DamageKind,
+    pub percent: i32,
+}
+
+/// resistances never add up to more than immunity
+pub const MAX_RESISTANCE: i32 = 100;
+
+/// `damage` after a resistance of `percent`
+pub fn resist(damage: i32, percent: i32) -> i32 {
+    damage * (100 - percent.min(MAX_RESISTANCE)) / 100
+}
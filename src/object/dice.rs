@@ -0,0 +1,59 @@
+use rand::Rng;
+
+use serde::{Deserialize, Serialize};
+
+/// A damage roll like "2d6": `count` dice with `sides` sides each, written
+/// that way in the data files and saves. No dice roll 0.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Dice {
+    pub count: i32,
+    pub sides: i32,
+}
+
+impl Dice {
+    pub const fn new(count: i32, sides: i32) -> Self {
+        Dice { count, sides }
+    }
+
+    pub fn roll<R: Rng>(self, rng: &mut R) -> i32 {
+        (0..self.count)
+            .map(|_| rng.gen_range(1, self.sides + 1))
+            .sum()
+    }
+}
+
+impl std::fmt::Display for Dice {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}d{}", self.count, self.sides)
+    }
+}
+
+impl std::str::FromStr for Dice {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid dice '{}', expected e.g. \"2d6\"", s);
+        let (count, sides) = s.trim().split_once('d').ok_or_else(invalid)?;
+        match (count.parse(), sides.parse()) {
+            (Ok(count), Ok(sides)) if count == 0 || (count > 0 && sides >= 1) => {
+                Ok(Dice::new(count, sides))
+            }
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl TryFrom<String> for Dice {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<Dice> for String {
+    fn from(dice: Dice) -> Self {
+        dice.to_string()
+    }
+}
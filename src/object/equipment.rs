@@ -1,5 +1,9 @@
 use crate::game::{Game, Tcod};
+use crate::object::damage::Resistance;
+use crate::object::dice::Dice;
 use crate::object::item::UseResult;
+use crate::object::ranged::Launcher;
+use crate::object::store::Objects;
 use crate::object::Object;
 
 use serde::{Deserialize, Serialize};
@@ -9,15 +13,44 @@ pub enum Slot {
     LeftHand,
     RightHand,
     Head,
+    Body,
+    Feet,
+    Hands,
+    Cloak,
+    /// rings fit on either hand, the template names one and the ring moves
+    /// to the other when it's taken
+    LeftRing,
+    RightRing,
+    Neck,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Equipment {
     pub slot: Slot,
+    #[serde(default)]
     pub equipped: bool,
+    #[serde(default)]
     pub max_hp_bonus: i32,
+    #[serde(default)]
     pub defense_bonus: i32,
+    #[serde(default)]
     pub power_bonus: i32,
+    /// damage roll of a weapon, in place of the wielder's own
+    #[serde(default)]
+    pub damage: Option<Dice>,
+    #[serde(default)]
+    pub accuracy_bonus: i32,
+    #[serde(default)]
+    pub evasion_bonus: i32,
+    /// protection against one kind of damage
+    #[serde(default)]
+    pub resistance: Option<Resistance>,
+    /// held in both hands, whatever its slot says
+    #[serde(default)]
+    pub two_handed: bool,
+    /// set for bows and crossbows
+    #[serde(default)]
+    pub launcher: Option<Launcher>,
 }
 
 impl std::fmt::Display for Slot {
@@ -26,31 +59,68 @@ impl std::fmt::Display for Slot {
             Slot::Head => write!(f, "head"),
             Slot::LeftHand => write!(f, "left hand"),
             Slot::RightHand => write!(f, "right hand"),
+            Slot::Body => write!(f, "body"),
+            Slot::Feet => write!(f, "feet"),
+            Slot::Hands => write!(f, "hands"),
+            Slot::Cloak => write!(f, "back"),
+            Slot::LeftRing => write!(f, "left ring finger"),
+            Slot::RightRing => write!(f, "right ring finger"),
+            Slot::Neck => write!(f, "neck"),
+        }
+    }
+}
+
+impl Slot {
+    /// every slot, in the order the paper doll lists them
+    pub const ALL: [Slot; 10] = [
+        Slot::Head,
+        Slot::Neck,
+        Slot::Cloak,
+        Slot::Body,
+        Slot::RightHand,
+        Slot::LeftHand,
+        Slot::Hands,
+        Slot::RightRing,
+        Slot::LeftRing,
+        Slot::Feet,
+    ];
+
+    /// the ring slot on the other hand
+    fn other_ring(self) -> Option<Slot> {
+        match self {
+            Slot::LeftRing => Some(Slot::RightRing),
+            Slot::RightRing => Some(Slot::LeftRing),
+            _ => None,
         }
     }
 }
 
 impl Equipment {
+    /// the slots the equipment takes up
+    pub fn slots(&self) -> Vec<Slot> {
+        if self.two_handed {
+            vec![Slot::RightHand, Slot::LeftHand]
+        } else {
+            vec![self.slot]
+        }
+    }
+
+    /// where the equipment is worn, for messages
+    pub fn worn_on(&self) -> String {
+        if self.two_handed {
+            "both hands".into()
+        } else {
+            self.slot.to_string()
+        }
+    }
+
     pub fn toggle(
         id: usize,
         _tcod: &mut Tcod,
         game: &mut Game,
-        _objects: &mut [Object],
+        objects: &mut Objects,
     ) -> UseResult {
-        let equipment = match game.inventory[id].equipment {
-            Some(equipment) => equipment,
-            None => return UseResult::Cancelled,
-        };
-
-        if equipment.equipped {
-            game.inventory[id].dequip(&mut game.messages);
-        } else {
-            if let Some(current) = Self::get_equipped_in_slot(equipment.slot, &game.inventory) {
-                game.inventory[current].dequip(&mut game.messages);
-            }
-            game.inventory[id].equip(&mut game.messages);
-        }
-
+        objects[game.player].toggle_equipment(id, &mut game.messages);
         UseResult::UsedAndKept
     }
 
@@ -59,11 +129,37 @@ impl Equipment {
             if item
                 .equipment
                 .as_ref()
-                .map_or(false, |e| e.equipped && e.slot == slot)
+                .map_or(false, |e| e.equipped && e.slots().contains(&slot))
             {
                 return Some(id);
             }
         }
         None
     }
+
+    /// a slot the equipment can go into without taking anything else off
+    pub fn free_slot(equipment: Equipment, inventory: &[Object]) -> Option<Slot> {
+        std::iter::once(equipment.slot)
+            .chain(equipment.slot.other_ring())
+            .find(|&slot| {
+                let placed = Equipment { slot, ..equipment };
+                placed
+                    .slots()
+                    .iter()
+                    .all(|&s| Self::get_equipped_in_slot(s, inventory).is_none())
+            })
+    }
+}
+
+/// what is worn where, one slot per line
+pub fn paper_doll(inventory: &[Object]) -> String {
+    Slot::ALL
+        .iter()
+        .map(|&slot| {
+            let worn = Equipment::get_equipped_in_slot(slot, inventory)
+                .map_or("-", |id| inventory[id].name.as_str());
+            format!("{}: {}", slot, worn)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
 }
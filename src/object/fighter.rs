@@ -1,4 +1,9 @@
+use crate::data;
+use crate::game::save::delete_slot;
 use crate::game::Game;
+use crate::object::damage::Resistance;
+use crate::object::dice::Dice;
+use crate::object::status::Status;
 use crate::object::Object;
 
 use tcod::colors::*;
@@ -6,14 +11,30 @@ use tcod::colors::*;
 use serde::{Deserialize, Serialize};
 
 // combat-related properties and methods (monster, player, NPC).
-#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Fighter {
     pub base_max_hp: i32,
     pub hp: i32,
     pub base_defense: i32,
     pub base_power: i32,
+    /// rolled for every hit without a weapon, on top of the power
+    pub damage: Dice,
+    /// added to the chance to hit, in percent
+    pub base_accuracy: i32,
+    /// taken off the attacker's chance to hit, in percent
+    pub base_evasion: i32,
     pub xp: i32,
     pub on_death: DeathCallback,
+    /// energy an attack takes
+    pub attack_cost: i32,
+    /// temporary effects, at most one of each kind
+    pub statuses: Vec<Status>,
+    /// given to whoever this fighter hits
+    pub on_hit: Option<Status>,
+    /// damage kinds the fighter is tough against or weak to
+    pub resistances: Vec<Resistance>,
+    /// id of the monster template whose loot is rolled on death
+    pub loot: Option<String>,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
@@ -36,6 +57,17 @@ impl DeathCallback {
 fn player_death(player: &mut Object, game: &mut Game) {
     game.messages.add("You died!", RED);
 
+    if game.permadeath {
+        match delete_slot(&game.slot) {
+            Ok(()) => game
+                .messages
+                .add("Your saved game dies with you.", DARK_RED),
+            Err(err) => game
+                .messages
+                .add(format!("Couldn't delete your saved game: {}", err), RED),
+        }
+    }
+
     player.glyph = '%';
     player.color = DARK_RED;
 }
@@ -45,11 +77,18 @@ fn monster_death(monster: &mut Object, game: &mut Game) {
         format!(
             "{} is dead! You gain {} experience points.",
             monster.name,
-            monster.fighter.unwrap().xp
+            monster.fighter.as_ref().unwrap().xp
         ),
         ORANGE,
     );
 
+    // the loot goes with the rest of what it carried, dropped around the body
+    let loot = monster.fighter.as_ref().and_then(|f| f.loot.as_ref());
+    if let Some(template) = loot.and_then(|id| data::get().monsters.get(id)) {
+        let loot = template.loot.roll(game.dungeon_level, &mut game.rng);
+        monster.inventory.extend(loot);
+    }
+
     monster.glyph = '%';
     monster.color = DARK_RED;
     monster.blocks = false;
@@ -0,0 +1,250 @@
+use crate::game::rng::GameRng;
+use crate::object::item::ItemTemplate;
+use crate::object::Object;
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use rand::Rng;
+use tcod::colors::Color;
+
+use serde::{Deserialize, Serialize};
+
+/// What kind of thing an item looks like until it's identified.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Unidentified {
+    Potion,
+    Scroll,
+}
+
+/// how unidentified potions can look, every kind of potion gets one of these
+const POTIONS: &[(&str, Color)] = &[
+    (
+        "murky",
+        Color {
+            r: 95,
+            g: 95,
+            b: 47,
+        },
+    ),
+    (
+        "fizzy",
+        Color {
+            r: 191,
+            g: 255,
+            b: 191,
+        },
+    ),
+    (
+        "bubbling",
+        Color {
+            r: 0,
+            g: 191,
+            b: 95,
+        },
+    ),
+    (
+        "smoky",
+        Color {
+            r: 127,
+            g: 127,
+            b: 127,
+        },
+    ),
+    (
+        "milky",
+        Color {
+            r: 255,
+            g: 255,
+            b: 223,
+        },
+    ),
+    (
+        "golden",
+        Color {
+            r: 255,
+            g: 191,
+            b: 0,
+        },
+    ),
+    (
+        "crimson",
+        Color {
+            r: 191,
+            g: 0,
+            b: 31,
+        },
+    ),
+    (
+        "oily",
+        Color {
+            r: 63,
+            g: 47,
+            b: 31,
+        },
+    ),
+    (
+        "glowing",
+        Color {
+            r: 255,
+            g: 255,
+            b: 127,
+        },
+    ),
+    (
+        "cloudy",
+        Color {
+            r: 191,
+            g: 191,
+            b: 223,
+        },
+    ),
+    (
+        "inky",
+        Color {
+            r: 31,
+            g: 31,
+            b: 95,
+        },
+    ),
+    (
+        "silvery",
+        Color {
+            r: 223,
+            g: 223,
+            b: 255,
+        },
+    ),
+];
+
+/// what scroll labels are made of
+const SYLLABLES: &[&str] = &[
+    "ab", "ra", "ca", "dab", "xy", "zzy", "fo", "bar", "nu", "ith", "el", "ber", "eth", "ze",
+    "lot", "ka", "zam", "vo", "qua", "mor",
+];
+
+/// What an unidentified kind of item is shown as.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Appearance {
+    pub name: String,
+    /// what a stack of them is called
+    pub plural: String,
+    pub color: Color,
+}
+
+/// What the player knows about the items of this game: what every kind of
+/// unidentified item looks like, shuffled for each game, and the kinds
+/// identified so far. Kinds without an appearance are always known.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Identities {
+    /// appearances by item template id
+    appearances: BTreeMap<String, Appearance>,
+    /// template ids of the kinds identified so far
+    known: BTreeSet<String>,
+}
+
+impl Identities {
+    /// give every kind of potion and scroll in `items` an appearance of its own
+    pub fn new(items: &HashMap<String, ItemTemplate>, rng: &mut GameRng) -> Self {
+        // sorted so the same seed always hands out the same appearances
+        let mut ids: Vec<&String> = items.keys().collect();
+        ids.sort();
+
+        let mut potions: Vec<(&str, Color)> = POTIONS.to_vec();
+        rng.shuffle(&mut potions);
+        let mut labels: Vec<String> = vec![];
+
+        let mut appearances = BTreeMap::new();
+        for id in ids {
+            let template = &items[id];
+            let appearance = match template.unidentified {
+                Some(Unidentified::Potion) => {
+                    let (look, color) = potions.pop().expect("Not enough potion appearances");
+                    Appearance {
+                        name: format!("{} potion", look),
+                        plural: format!("{} potions", look),
+                        color,
+                    }
+                }
+                Some(Unidentified::Scroll) => {
+                    let label = loop {
+                        let label = scroll_label(rng);
+                        if !labels.contains(&label) {
+                            break label;
+                        }
+                    };
+                    labels.push(label.clone());
+                    Appearance {
+                        name: format!("scroll labelled {}", label),
+                        plural: format!("scrolls labelled {}", label),
+                        color: template.color,
+                    }
+                }
+                None => continue,
+            };
+            appearances.insert(id.clone(), appearance);
+        }
+        Identities {
+            appearances,
+            known: BTreeSet::new(),
+        }
+    }
+
+    /// what the item with template `id` looks like, if the player doesn't know what it is
+    pub fn disguise(&self, id: &str) -> Option<&Appearance> {
+        self.appearances
+            .get(id)
+            .filter(|_| !self.known.contains(id))
+    }
+
+    /// learn what the items with template `id` are, `true` if it wasn't known yet
+    pub fn identify(&mut self, id: &str) -> bool {
+        self.disguise(id).is_some() && self.known.insert(id.into())
+    }
+
+    /// the name of `object` as far as the player knows, with the size of its stack
+    pub fn name(&self, object: &Object) -> String {
+        let item = object.item.as_ref();
+        match item.and_then(|i| self.disguise(&i.id)) {
+            Some(appearance) if item.is_some_and(|i| i.count > 1) => {
+                format!("{} {}", item.unwrap().count, appearance.plural)
+            }
+            Some(appearance) => appearance.name.clone(),
+            None => object.stack_name(),
+        }
+    }
+
+    /// the color `object` is drawn in, potions keep theirs even once identified
+    pub fn color(&self, object: &Object) -> Color {
+        object
+            .item
+            .as_ref()
+            .and_then(|i| self.appearances.get(&i.id))
+            .map_or(object.color, |appearance| appearance.color)
+    }
+}
+
+/// two made up words like "ZEBAR NUITH"
+fn scroll_label(rng: &mut GameRng) -> String {
+    let mut word = || {
+        (0..rng.gen_range(2, 4))
+            .map(|_| SYLLABLES[rng.gen_range(0, SYLLABLES.len())])
+            .collect::<String>()
+    };
+    format!("{} {}", word(), word()).to_uppercase()
+}
+
+/// there have to be enough appearances for every kind of potion
+pub fn check_appearances(items: &HashMap<String, ItemTemplate>) -> Result<(), String> {
+    let potions = items
+        .values()
+        .filter(|t| t.unidentified == Some(Unidentified::Potion))
+        .count();
+    if potions > POTIONS.len() {
+        return Err(format!(
+            "{} kinds of unidentified potions but only {} appearances for them",
+            potions,
+            POTIONS.len()
+        ));
+    }
+    Ok(())
+}
@@ -1,112 +1,176 @@
-use crate::config::PLAYER;
+use crate::command::replay::Input;
+use crate::data;
 use crate::game::map::is_out_of_bounds;
-use crate::game::{render_all, Game, Tcod};
-use crate::object::ai::Ai;
-use crate::object::equipment::{Equipment, Slot};
+use crate::game::{inventory_menu, render_all, Game, Tcod};
+use crate::object::damage::DamageKind;
+use crate::object::equipment::Equipment;
+use crate::object::identify::Unidentified;
+use crate::object::loot::drop_loot;
+use crate::object::ranged::Ammo;
+use crate::object::status::{apply_status, Status, StatusKind};
+use crate::object::store::{Entity, Objects};
 use crate::object::Object;
 
 use tcod::colors::*;
-use tcod::input::{self, Event};
+use tcod::input::Event;
 
 use serde::{Deserialize, Serialize};
 
-const HEAL_AMOUNT: i32 = 40;
-const LIGHTNING_DAMAGE: i32 = 40;
-const LIGHTNING_RANGE: i32 = 5;
-const CONFUSE_RANGE: i32 = 8;
-const CONFUSE_NUM_TURNS: i32 = 10;
-const FIREBALL_RADIUS: i32 = 3;
-const FIREBALL_DAMAGE: i32 = 25;
-
+/// What using an item does, with the numbers taken from its template.
 #[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
-pub enum Item {
-    Heal,
-    Lightning,
-    Confusion,
-    Fireball,
-    Sword,
-    Shield,
+pub enum Effect {
+    Heal {
+        amount: i32,
+    },
+    Lightning {
+        damage: i32,
+        range: i32,
+    },
+    Confusion {
+        range: i32,
+        turns: i32,
+    },
+    Fireball {
+        damage: i32,
+        radius: i32,
+    },
+    /// gives a status to the user, or to a monster within `range` if there is one
+    Status {
+        status: Status,
+        #[serde(default)]
+        range: Option<i32>,
+    },
+    /// tells what an item in the inventory is
+    Identify,
+}
+
+impl Effect {
+    fn cast(self, tcod: &mut Tcod, game: &mut Game, objects: &mut Objects) -> UseResult {
+        use Effect::*;
+        match self {
+            Heal { amount } => cast_heal(amount, tcod, game, objects),
+            Lightning { damage, range } => cast_lightning(damage, range, tcod, game, objects),
+            Confusion { range, turns } => cast_confusion(range, turns, tcod, game, objects),
+            Fireball { damage, radius } => cast_fireball(damage, radius, tcod, game, objects),
+            Status { status, range } => cast_status(status, range, tcod, game, objects),
+            Identify => cast_identify(tcod, game, objects),
+        }
+    }
+}
+
+/// A kind of item as described in the items data file.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ItemTemplate {
+    pub name: String,
+    pub glyph: char,
+    pub color: Color,
+    #[serde(default)]
+    pub effect: Option<Effect>,
+    #[serde(default)]
+    pub equipment: Option<Equipment>,
+    #[serde(default)]
+    pub ammo: Option<Ammo>,
+    /// what a stack of them is called, the name with an "s" by default
+    #[serde(default)]
+    pub plural: Option<String>,
+    /// how many are found together
+    #[serde(default = "single")]
+    pub count: i32,
+    /// what it looks like until identified, `None` if it's always known
+    #[serde(default)]
+    pub unidentified: Option<Unidentified>,
+}
+
+fn single() -> i32 {
+    1
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Item {
+    /// id of the template the item was created from
+    pub id: String,
+    pub effect: Option<Effect>,
+    #[serde(default)]
+    pub ammo: Option<Ammo>,
+    /// how many there are in the stack
+    pub count: i32,
 }
 
 impl Item {
-    pub fn create(item: Item, x: i32, y: i32) -> Object {
-        match item {
-            Item::Heal => {
-                let mut object = Object::new(x, y, '!', VIOLET, "healing potion", false);
-                object.item = Some(Item::Heal);
-                object
-            }
-            Item::Shield => {
-                let mut object = Object::new(x, y, 'o', DARKER_ORANGE, "shield", false);
-                object.item = Some(Item::Shield);
-                object.equipment = Some(Equipment {
-                    equipped: false,
-                    slot: Slot::LeftHand,
-                    max_hp_bonus: 0,
-                    defense_bonus: 1,
-                    power_bonus: 0,
-                });
-                object
-            }
-            Item::Sword => {
-                let mut object = Object::new(x, y, '/', SKY, "sword", false);
-                object.item = Some(Item::Sword);
-                object.equipment = Some(Equipment {
-                    equipped: false,
-                    slot: Slot::RightHand,
-                    max_hp_bonus: 0,
-                    defense_bonus: 0,
-                    power_bonus: 3,
-                });
-                object
-            }
-            Item::Lightning => {
-                let mut object =
-                    Object::new(x, y, '#', LIGHT_YELLOW, "scroll of lightning bolt", false);
-                object.item = Some(Item::Lightning);
-                object
+    /// create the item with the given template id at (x, y)
+    pub fn create(id: &str, x: i32, y: i32) -> Object {
+        let template = data::get()
+            .items
+            .get(id)
+            .unwrap_or_else(|| panic!("Unknown item '{}'", id));
+
+        let mut object = Object::new(x, y, template.glyph, template.color, &template.name, false);
+        object.item = Some(Item {
+            id: id.into(),
+            effect: template.effect,
+            ammo: template.ammo,
+            count: template.count,
+        });
+        object.equipment = template.equipment;
+        object
+    }
+
+    /// "healing potion", or "3 healing potions" for a stack of them
+    pub fn stack_name(&self, name: &str) -> String {
+        if self.count == 1 {
+            return name.into();
+        }
+        let plural = data::get()
+            .items
+            .get(&self.id)
+            .and_then(|template| template.plural.clone())
+            .unwrap_or_else(|| format!("{}s", name));
+        format!("{} {}", self.count, plural)
+    }
+
+    pub fn use_item(id: usize, tcod: &mut Tcod, game: &mut Game, objects: &mut Objects) {
+        let item = &objects[game.player].inventory[id];
+        let effect = item.item.as_ref().and_then(|i| i.effect);
+        let kind = item.item.as_ref().map(|i| i.id.clone()).unwrap_or_default();
+        let name = item.name.clone();
+        let disguise = game.identities.disguise(&kind).map(|a| a.name.clone());
+        let result = match (effect, item.equipment) {
+            (Some(effect), _) => effect.cast(tcod, game, objects),
+            (None, Some(_)) => Equipment::toggle(id, tcod, game, objects),
+            (None, None) => {
+                let message = format!("The {} can't be used.", item.name);
+                game.messages.add(message, WHITE);
+                return;
             }
-            Item::Fireball => {
-                // create a fireball scroll (10% chance)
-                let mut object = Object::new(x, y, '#', LIGHT_YELLOW, "scroll of fireball", false);
-                object.item = Some(Item::Fireball);
-                object
+        };
+        // trying out an unknown item uses it up even if nothing comes of it,
+        // or cancelling would tell what it is for free
+        let result = match result {
+            UseResult::Cancelled if disguise.is_some() => UseResult::UsedUp,
+            result => result,
+        };
+        match result {
+            UseResult::UsedUp => {
+                let inventory = &mut objects[game.player].inventory;
+                let item = inventory[id].item.as_mut().unwrap();
+                item.count -= 1;
+                if item.count == 0 {
+                    inventory.remove(id);
+                }
             }
-            Item::Confusion => {
-                // create a confusion scroll (10% chance)
-                let mut object = Object::new(x, y, '#', LIGHT_YELLOW, "scroll of confusion", false);
-                object.item = Some(Item::Confusion);
-                object
+            UseResult::UsedAndKept => {}
+            UseResult::Cancelled => {
+                game.messages.add("Cancelled", WHITE);
+                return;
             }
         }
-    }
-
-    pub fn use_item(id: usize, tcod: &mut Tcod, game: &mut Game, objects: &mut [Object]) {
-        use crate::object::item::Item::*;
 
-        if let Some(item) = game.inventory[id].item {
-            let on_use = match item {
-                Heal => cast_heal,
-                Lightning => cast_lightning,
-                Confusion => cast_confusion,
-                Fireball => cast_fireball,
-                Sword => Equipment::toggle,
-                Shield => Equipment::toggle,
-            };
-            match on_use(id, tcod, game, objects) {
-                UseResult::UsedUp => {
-                    game.inventory.remove(id);
-                }
-                UseResult::UsedAndKept => {}
-                UseResult::Cancelled => {
-                    game.messages.add("Cancelled", WHITE);
-                }
+        // using it gives away what it is
+        if let Some(disguise) = disguise {
+            if game.identities.identify(&kind) {
+                game.messages
+                    .add(format!("The {} was a {}.", disguise, name), LIGHT_CYAN);
             }
-        } else {
-            game.messages.add(
-                format!("The {} can't be used.", game.inventory[id].name),
-                WHITE,
-            );
         }
     }
 }
@@ -117,38 +181,41 @@ pub enum UseResult {
     UsedAndKept,
 }
 
-fn cast_heal(_id: usize, _tcod: &mut Tcod, game: &mut Game, objects: &mut [Object]) -> UseResult {
-    let player = &mut objects[PLAYER];
-    if let Some(fighter) = player.fighter {
-        if fighter.hp == player.max_hp(game) {
+fn cast_heal(amount: i32, _tcod: &mut Tcod, game: &mut Game, objects: &mut Objects) -> UseResult {
+    let player = &mut objects[game.player];
+    if let Some(fighter) = player.fighter.as_ref() {
+        if fighter.hp == player.max_hp() {
             game.messages.add("You are already at full health", RED);
             return UseResult::Cancelled;
         }
         game.messages
             .add("Youre wounds start to feel better!", LIGHT_VIOLET);
-        objects[PLAYER].heal(HEAL_AMOUNT, game);
+        objects[game.player].heal(amount);
         return UseResult::UsedUp;
     }
     UseResult::Cancelled
 }
 
 fn cast_lightning(
-    _id: usize,
+    damage: i32,
+    range: i32,
     tcod: &mut Tcod,
     game: &mut Game,
-    objects: &mut [Object],
+    objects: &mut Objects,
 ) -> UseResult {
-    if let Some(id) = target_closest(tcod, objects, LIGHTNING_RANGE) {
+    if let Some(id) = target_closest(tcod, game, objects, range) {
+        let dealt = objects[id].resisted(damage, DamageKind::Lightning);
         game.messages.add(
             format!(
                 "A lightning bolt strikes the {} with a loud thunder! \
                                            The damage is {} git points.",
-                objects[id].name, LIGHTNING_DAMAGE
+                objects[id].name, dealt
             ),
             LIGHT_BLUE,
         );
-        if let Some(xp) = objects[id].take_damage(LIGHTNING_DAMAGE, game) {
-            objects[PLAYER].fighter.as_mut().unwrap().xp += xp;
+        if let Some(xp) = objects[id].take_damage(damage, DamageKind::Lightning, game) {
+            objects[game.player].fighter.as_mut().unwrap().xp += xp;
+            drop_loot(id, game, objects);
         }
         UseResult::UsedUp
     } else {
@@ -159,16 +226,17 @@ fn cast_lightning(
 }
 
 fn cast_confusion(
-    _id: usize,
+    range: i32,
+    turns: i32,
     tcod: &mut Tcod,
     game: &mut Game,
-    objects: &mut [Object],
+    objects: &mut Objects,
 ) -> UseResult {
     game.messages.add(
         "Left-click an enemy to confuse it, or right-click to cancel.",
         LIGHT_CYAN,
     );
-    if let Some(id) = target_monster(tcod, game, objects, Some(CONFUSE_RANGE as f32)) {
+    if let Some(id) = target_monster(tcod, game, objects, Some(range as f32)) {
         game.messages.add(
             format!(
                 "The eyes of {} look vacant, as he starts to stumble around!",
@@ -176,22 +244,79 @@ fn cast_confusion(
             ),
             LIGHT_GREEN,
         );
-        let old_ai = objects[id].ai.take().unwrap_or(Ai::Basic);
-        objects[id].ai = Some(Ai::Confused {
-            previous_ai: Box::new(old_ai),
-            lasts_for: CONFUSE_NUM_TURNS,
-        });
+        let confusion = Status {
+            kind: StatusKind::Confusion,
+            turns,
+            potency: 0,
+        };
+        apply_status(&mut objects[id], confusion, game);
         UseResult::UsedUp
     } else {
         UseResult::Cancelled
     }
 }
 
+fn cast_status(
+    status: Status,
+    range: Option<i32>,
+    tcod: &mut Tcod,
+    game: &mut Game,
+    objects: &mut Objects,
+) -> UseResult {
+    let range = match range {
+        Some(range) => range,
+        None => {
+            apply_status(&mut objects[game.player], status, game);
+            return UseResult::UsedUp;
+        }
+    };
+    game.messages.add(
+        "Left-click an enemy to target it, or right-click to cancel.",
+        LIGHT_CYAN,
+    );
+    match target_monster(tcod, game, objects, Some(range as f32)) {
+        Some(id) => {
+            apply_status(&mut objects[id], status, game);
+            UseResult::UsedUp
+        }
+        None => UseResult::Cancelled,
+    }
+}
+
+fn cast_identify(tcod: &mut Tcod, game: &mut Game, objects: &mut Objects) -> UseResult {
+    let choice = inventory_menu(
+        "Press the key next to an item to identify it, or any other to cancel\n",
+        tcod,
+        game,
+        objects,
+    );
+    let item = match choice {
+        Some(index) => &objects[game.player].inventory[index],
+        None => return UseResult::Cancelled,
+    };
+    let kind = item.item.as_ref().map_or("", |i| i.id.as_str());
+    let disguise = game.identities.disguise(kind).map(|a| a.name.clone());
+    match disguise {
+        Some(disguise) => {
+            let message = format!("The {} is a {}.", disguise, item.name);
+            game.identities.identify(kind);
+            game.messages.add(message, LIGHT_CYAN);
+            UseResult::UsedUp
+        }
+        None => {
+            let message = format!("You already know that's a {}.", item.name);
+            game.messages.add(message, WHITE);
+            UseResult::Cancelled
+        }
+    }
+}
+
 fn cast_fireball(
-    _id: usize,
+    damage: i32,
+    radius: i32,
     tcod: &mut Tcod,
     game: &mut Game,
-    objects: &mut [Object],
+    objects: &mut Objects,
 ) -> UseResult {
     game.messages.add(
         "Left-click a target tile for the fireball, or right-click to cancel.",
@@ -204,44 +329,51 @@ fn cast_fireball(
     game.messages.add(
         format!(
             "The fireball explodes, burning everything within {} tiles!",
-            FIREBALL_RADIUS
+            radius
         ),
         ORANGE,
     );
 
     let mut xp_to_gain = 0;
-    for (id, obj) in objects.iter_mut().enumerate() {
-        if obj.distance(x, y) <= FIREBALL_RADIUS as f32 && obj.fighter.is_some() {
-            game.messages.add(
-                format!(
-                    "The {} gets burned for {} hit points.",
-                    obj.name, FIREBALL_DAMAGE
-                ),
-                ORANGE,
-            );
-            if let Some(xp) = obj.take_damage(FIREBALL_DAMAGE, game) {
+    let mut killed = vec![];
+    for (id, obj) in objects.iter_mut() {
+        if obj.distance(x, y) <= radius as f32 && obj.fighter.is_some() {
+            let dealt = obj.resisted(damage, DamageKind::Fire);
+            let message = if dealt > 0 {
+                format!("The {} gets burned for {} hit points.", obj.name, dealt)
+            } else {
+                format!("The {} is unharmed by the flames.", obj.name)
+            };
+            game.messages.add(message, ORANGE);
+            if let Some(xp) = obj.take_damage(damage, DamageKind::Fire, game) {
+                killed.push(id);
                 // Not getting any xp for commiting suicide
-                if id != PLAYER {
+                if id != game.player {
                     xp_to_gain += xp;
+                } else {
+                    game.killed_by = Some("their own fireball".into());
                 }
             }
         }
     }
-    objects[PLAYER].fighter.as_mut().unwrap().xp += xp_to_gain;
+    objects[game.player].fighter.as_mut().unwrap().xp += xp_to_gain;
+    for id in killed {
+        drop_loot(id, game, objects);
+    }
 
     UseResult::UsedUp
 }
 
-fn target_closest(tcod: &Tcod, objects: &[Object], max_range: i32) -> Option<usize> {
+fn target_closest(tcod: &Tcod, game: &Game, objects: &Objects, max_range: i32) -> Option<Entity> {
     let mut closest_enemy = None;
     let mut closest_distance = (max_range + 1) as f32;
-    for (id, object) in objects.iter().enumerate() {
-        if id != PLAYER
+    for (id, object) in objects.iter() {
+        if id != game.player
             && object.fighter.is_some()
             && object.ai.is_some()
             && tcod.fov.is_in_fov(object.x, object.y)
         {
-            let dist = objects[PLAYER].distance_to(object);
+            let dist = objects[game.player].distance_to(object);
             if dist < closest_distance {
                 closest_enemy = Some(id);
                 closest_distance = dist;
@@ -252,20 +384,34 @@ fn target_closest(tcod: &Tcod, objects: &[Object], max_range: i32) -> Option<usi
 }
 
 /// return the position of a tile left-clicked in player's FOV (optionally in a
-/// range), or (None,None) if right-clicked.
-fn target_tile(
+/// range), or (None,None) if right-clicked. The driver picks instead if there is
+/// one, and either way the pick goes into the replay.
+pub fn target_tile(
+    tcod: &mut Tcod,
+    game: &mut Game,
+    objects: &Objects,
+    max_range: Option<f32>,
+) -> Option<(i32, i32)> {
+    let target = match tcod.driver.as_mut() {
+        Some(driver) => driver.target(game, objects, max_range),
+        None => click_tile(tcod, game, objects, max_range),
+    };
+    game.record(Input::Target(target));
+    target
+}
+
+fn click_tile(
     tcod: &mut Tcod,
     game: &mut Game,
-    objects: &[Object],
+    objects: &Objects,
     max_range: Option<f32>,
 ) -> Option<(i32, i32)> {
     use tcod::input::KeyCode::Escape;
-    loop {
+    while !tcod.backend.window_closed() {
         // render the screen. this erases the inventory and shows the names of
         // objects under the mouse.
-        tcod.root.flush();
-        let event = input::check_for_event(input::KEY_PRESS | input::MOUSE).map(|e| e.1);
-        match event {
+        tcod.backend.flush();
+        match tcod.backend.check_for_event() {
             Some(Event::Mouse(m)) => tcod.mouse = m,
             Some(Event::Key(k)) => tcod.key = k,
             None => tcod.key = Default::default(),
@@ -275,7 +421,7 @@ fn target_tile(
         let (x, y) = (tcod.mouse.cx as i32, tcod.mouse.cy as i32);
 
         let in_fov = !is_out_of_bounds(x, y) && tcod.fov.is_in_fov(x, y);
-        let in_range = max_range.map_or(true, |r| objects[PLAYER].distance(x, y) <= r);
+        let in_range = max_range.map_or(true, |r| objects[game.player].distance(x, y) <= r);
         if tcod.mouse.lbutton_pressed && in_fov && in_range {
             return Some((x, y));
         }
@@ -284,20 +430,21 @@ fn target_tile(
             return None;
         }
     }
+    None
 }
 
 fn target_monster(
     tcod: &mut Tcod,
     game: &mut Game,
-    objects: &[Object],
+    objects: &Objects,
     max_range: Option<f32>,
-) -> Option<usize> {
+) -> Option<Entity> {
     loop {
         match target_tile(tcod, game, objects, max_range) {
             Some((x, y)) => {
                 // return the first clicked monster, otherwise continue looping
-                for (id, obj) in objects.iter().enumerate() {
-                    if obj.pos() == (x, y) && obj.fighter.is_some() && id != PLAYER {
+                for (id, obj) in objects.iter() {
+                    if obj.pos() == (x, y) && obj.fighter.is_some() && id != game.player {
                         return Some(id);
                     }
                 }
@@ -0,0 +1,130 @@
+use crate::game::map::{
+    check_chances, check_order, from_dungeon_level, is_out_of_bounds, weighted_ids, SpawnChance,
+    Transition,
+};
+use crate::game::rng::GameRng;
+use crate::game::Game;
+use crate::object::item::Item;
+use crate::object::store::{Entity, Objects};
+use crate::object::Object;
+
+use rand::distributions::{IndependentSample, Weighted, WeightedChoice};
+use rand::Rng;
+
+use serde::{Deserialize, Serialize};
+
+/// template id of gold piles
+pub const GOLD: &str = "gold";
+
+/// how far from where a monster died its loot can land
+const MAX_SCATTER: i32 = 2;
+
+/// What a monster drops when it dies, every number by depth like the spawn tables.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Loot {
+    /// item ids dropped every time
+    #[serde(default)]
+    pub guaranteed: Vec<String>,
+    /// how many times to pick from `drops`
+    #[serde(default)]
+    pub rolls: Vec<Transition>,
+    /// weight of a roll dropping nothing at all
+    #[serde(default)]
+    pub nothing: Vec<Transition>,
+    #[serde(default)]
+    pub drops: Vec<SpawnChance>,
+    /// most gold dropped, at least one piece if it's above 0
+    #[serde(default)]
+    pub gold: Vec<Transition>,
+}
+
+impl Loot {
+    /// make sure the table only drops items that exist and its levels are in order
+    pub fn validate(&self, item_ids: &[&str]) -> Result<(), String> {
+        if let Some(id) = self
+            .guaranteed
+            .iter()
+            .find(|id| !item_ids.contains(&id.as_str()))
+        {
+            return Err(format!("Unknown item '{}' in loot", id));
+        }
+        check_chances("item", &self.drops, item_ids)?;
+        check_order("rolls", &self.rolls)?;
+        check_order("nothing", &self.nothing)?;
+        check_order("gold", &self.gold)
+    }
+
+    /// roll the table for a monster killed on dungeon `level`
+    pub fn roll(&self, level: u32, rng: &mut GameRng) -> Vec<Object> {
+        let mut loot: Vec<Object> = self
+            .guaranteed
+            .iter()
+            .map(|id| Item::create(id, 0, 0))
+            .collect();
+
+        let mut chances = weighted_ids(&self.drops, level);
+        chances.push(Weighted {
+            weight: from_dungeon_level(&self.nothing, level),
+            item: "",
+        });
+        if chances.iter().any(|chance| chance.weight > 0) {
+            let choice = WeightedChoice::new(&mut chances);
+            for _ in 0..from_dungeon_level(&self.rolls, level) {
+                match choice.ind_sample(rng) {
+                    "" => {}
+                    id => loot.push(Item::create(id, 0, 0)),
+                }
+            }
+        }
+
+        let max_gold = from_dungeon_level(&self.gold, level) as i32;
+        if max_gold > 0 {
+            loot.push(gold_pile(rng.gen_range(1, max_gold + 1)));
+        }
+        loot
+    }
+}
+
+/// a pile of `amount` gold pieces
+pub fn gold_pile(amount: i32) -> Object {
+    let mut gold = Item::create(GOLD, 0, 0);
+    gold.gold = amount;
+    gold.name = format!("{} {}", amount, gold.name);
+    gold
+}
+
+/// Spill what the monster `id` just killed was carrying on the floor around
+/// it: its own gear and the loot rolled when it died. Each item gets a tile
+/// of its own if there is one close by.
+pub fn drop_loot(id: Entity, game: &Game, objects: &mut Objects) {
+    if id == game.player {
+        return;
+    }
+    let (x, y) = objects[id].pos();
+    let items = std::mem::take(&mut objects[id].inventory);
+    for mut item in items {
+        let (x, y) = free_tile(x, y, game, objects).unwrap_or((x, y));
+        if let Some(equipment) = item.equipment.as_mut() {
+            equipment.equipped = false;
+        }
+        item.set_pos(x, y);
+        item.always_visible = true;
+        objects.insert(item);
+    }
+}
+
+/// the closest floor tile to (x, y) without an item or anything blocking on it
+fn free_tile(x: i32, y: i32, game: &Game, objects: &Objects) -> Option<(i32, i32)> {
+    (0..=MAX_SCATTER).find_map(|distance| {
+        let mut ring = (-distance..=distance)
+            .flat_map(|dx| (-distance..=distance).map(move |dy| (x + dx, y + dy)))
+            .filter(|&(rx, ry)| (rx - x).abs() == distance || (ry - y).abs() == distance);
+        ring.find(|&(x, y)| {
+            !is_out_of_bounds(x, y)
+                && !game.map[x as usize][y as usize].blocked
+                && !objects
+                    .iter()
+                    .any(|(_, o)| o.pos() == (x, y) && (o.blocks || o.item.is_some()))
+        })
+    })
+}
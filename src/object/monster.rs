@@ -1,50 +1,100 @@
+use crate::data;
 use crate::object::ai::Ai;
+use crate::object::damage::Resistance;
+use crate::object::dice::Dice;
 use crate::object::fighter::{DeathCallback, Fighter};
-use crate::object::Object;
+use crate::object::item::Item;
+use crate::object::loot::Loot;
+use crate::object::status::Status;
+use crate::object::{Object, ACTION_COST, NORMAL_SPEED};
 
-use tcod::colors::*;
+use tcod::colors::Color;
 
-#[derive(Clone, Copy, Debug)]
-pub enum Monster {
-    Orc,
-    Troll,
+use serde::{Deserialize, Serialize};
+
+/// A kind of monster as described in the monsters data file.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Monster {
+    pub name: String,
+    pub glyph: char,
+    pub color: Color,
+    pub hp: i32,
+    pub defense: i32,
+    pub power: i32,
+    /// rolled for every hit, on top of the power
+    #[serde(default)]
+    pub damage: Dice,
+    /// added to the chance to hit, in percent
+    #[serde(default)]
+    pub accuracy: i32,
+    /// taken off the attacker's chance to hit, in percent
+    #[serde(default)]
+    pub evasion: i32,
+    pub xp: i32,
+    pub ai: Ai,
+    /// energy gained every turn, 100 is as fast as the player
+    #[serde(default = "normal_speed")]
+    pub speed: i32,
+    /// energy an attack takes, 100 is as long as a step
+    #[serde(default = "action_cost")]
+    pub attack_cost: i32,
+    /// status given to whoever the monster hits, e.g. poison
+    #[serde(default)]
+    pub on_hit: Option<Status>,
+    /// e.g. `{ "kind": "Fire", "percent": 100 }` for immunity to fire
+    #[serde(default)]
+    pub resistances: Vec<Resistance>,
+    /// ids of the items the monster spawns wearing and wielding
+    #[serde(default)]
+    pub equipment: Vec<String>,
+    /// what it drops when it dies, besides its equipment
+    #[serde(default)]
+    pub loot: Loot,
 }
 
-impl Monster {
-    pub fn create(monster: Monster, x: i32, y: i32) -> Object {
-        match monster {
-            Monster::Orc => create_orc(x, y),
-            Monster::Troll => create_troll(x, y),
-        }
-    }
+fn normal_speed() -> i32 {
+    NORMAL_SPEED
 }
 
-fn create_orc(x: i32, y: i32) -> Object {
-    let mut orc = Object::new(x, y, 'o', DESATURATED_GREEN, "orc", true);
-    orc.alive = true;
-    orc.fighter = Some(Fighter {
-        base_max_hp: 20,
-        hp: 20,
-        base_defense: 0,
-        base_power: 4,
-        xp: 35,
-        on_death: DeathCallback::Monster,
-    });
-    orc.ai = Some(Ai::Basic);
-    orc
+fn action_cost() -> i32 {
+    ACTION_COST
 }
 
-fn create_troll(x: i32, y: i32) -> Object {
-    let mut troll = Object::new(x, y, 'T', DARKER_GREEN, "troll", true);
-    troll.alive = true;
-    troll.fighter = Some(Fighter {
-        base_max_hp: 30,
-        hp: 30,
-        base_defense: 2,
-        base_power: 8,
-        xp: 100,
-        on_death: DeathCallback::Monster,
-    });
-    troll.ai = Some(Ai::Basic);
-    troll
+impl Monster {
+    /// spawn the monster with the given template id at (x, y)
+    pub fn create(id: &str, x: i32, y: i32) -> Object {
+        let template = data::get()
+            .monsters
+            .get(id)
+            .unwrap_or_else(|| panic!("Unknown monster '{}'", id));
+
+        let mut monster = Object::new(x, y, template.glyph, template.color, &template.name, true);
+        monster.alive = true;
+        monster.fighter = Some(Fighter {
+            base_max_hp: template.hp,
+            hp: template.hp,
+            base_defense: template.defense,
+            base_power: template.power,
+            damage: template.damage,
+            base_accuracy: template.accuracy,
+            base_evasion: template.evasion,
+            xp: template.xp,
+            on_death: DeathCallback::Monster,
+            attack_cost: template.attack_cost,
+            statuses: vec![],
+            on_hit: template.on_hit,
+            resistances: template.resistances.clone(),
+            loot: Some(id.into()),
+        });
+        for id in &template.equipment {
+            let mut item = Item::create(id, x, y);
+            if let Some(equipment) = item.equipment.as_mut() {
+                equipment.equipped = true;
+            }
+            monster.inventory.push(item);
+        }
+        monster.ai = Some(template.ai.clone());
+        monster.base_speed = template.speed;
+        monster
+    }
 }
@@ -0,0 +1,158 @@
+use crate::game::map::is_out_of_bounds;
+use crate::game::{Game, Tcod};
+use crate::object::dice::Dice;
+use crate::object::item::target_tile;
+use crate::object::loot::drop_loot;
+use crate::object::store::Objects;
+
+use tcod::colors::*;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AmmoKind {
+    Arrow,
+    Bolt,
+}
+
+impl std::fmt::Display for AmmoKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            AmmoKind::Arrow => write!(f, "arrows"),
+            AmmoKind::Bolt => write!(f, "bolts"),
+        }
+    }
+}
+
+impl AmmoKind {
+    /// what a single shot is called
+    fn projectile(self) -> &'static str {
+        match self {
+            AmmoKind::Arrow => "arrow",
+            AmmoKind::Bolt => "bolt",
+        }
+    }
+}
+
+/// What makes a piece of equipment a bow or a crossbow.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Launcher {
+    pub ammo: AmmoKind,
+    pub range: i32,
+    /// damage roll of a shot
+    #[serde(default)]
+    pub damage: Dice,
+    /// added to the shooter's power, on top of the other equipment
+    #[serde(default)]
+    pub power_bonus: i32,
+    /// energy a shot takes
+    pub cost: i32,
+}
+
+/// What makes an item arrows or bolts, how many is the count of their stack.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Ammo {
+    pub kind: AmmoKind,
+}
+
+/// Shoot with the equipped launcher at a tile the player picks. Returns the
+/// energy the shot took, or `None` if nothing was shot.
+pub fn fire(tcod: &mut Tcod, game: &mut Game, objects: &mut Objects) -> Option<i32> {
+    let launcher = objects[game.player]
+        .inventory
+        .iter()
+        .filter_map(|item| item.equipment)
+        .find(|e| e.equipped && e.launcher.is_some())
+        .and_then(|e| e.launcher);
+    let launcher = match launcher {
+        Some(launcher) => launcher,
+        None => {
+            game.messages.add("You have nothing to shoot with.", WHITE);
+            return None;
+        }
+    };
+    let quiver = objects[game.player].inventory.iter().position(|item| {
+        item.item
+            .as_ref()
+            .and_then(|i| i.ammo)
+            .is_some_and(|ammo| ammo.kind == launcher.ammo)
+    });
+    let quiver = match quiver {
+        Some(quiver) => quiver,
+        None => {
+            game.messages
+                .add(format!("You are out of {}.", launcher.ammo), WHITE);
+            return None;
+        }
+    };
+
+    game.messages.add(
+        "Left-click a target tile to shoot at, or right-click to cancel.",
+        LIGHT_CYAN,
+    );
+    let target = target_tile(tcod, game, objects, Some(launcher.range as f32))?;
+    if target == objects[game.player].pos() {
+        game.messages.add("You can't shoot yourself.", WHITE);
+        return None;
+    }
+
+    // the shot is fired, whatever it hits
+    let inventory = &mut objects[game.player].inventory;
+    let ammo = inventory[quiver].item.as_mut().unwrap();
+    ammo.count -= 1;
+    if ammo.count == 0 {
+        inventory.remove(quiver);
+    }
+
+    let projectile = launcher.ammo.projectile();
+    for (x, y) in line(objects[game.player].pos(), target) {
+        if is_out_of_bounds(x, y) || game.map[x as usize][y as usize].blocked {
+            game.messages
+                .add(format!("The {} hits the wall.", projectile), WHITE);
+            return Some(launcher.cost);
+        }
+        let hit = objects
+            .iter()
+            .find(|&(id, o)| id != game.player && o.blocks && o.pos() == (x, y))
+            .map(|(id, _)| id);
+        if let Some(id) = hit {
+            if objects[id].fighter.is_some() {
+                let (player, target) = objects.get_two_mut(game.player, id);
+                if player.shoot(target, launcher, game) {
+                    drop_loot(id, game, objects);
+                }
+            } else {
+                game.messages.add(
+                    format!("The {} hits the {}.", projectile, objects[id].name),
+                    WHITE,
+                );
+            }
+            return Some(launcher.cost);
+        }
+    }
+    game.messages
+        .add(format!("The {} hits nothing.", projectile), WHITE);
+    Some(launcher.cost)
+}
+
+/// the tiles on a straight line from `from` to `to`, without `from` itself
+fn line(from: (i32, i32), to: (i32, i32)) -> Vec<(i32, i32)> {
+    let (mut x, mut y) = from;
+    let (dx, dy) = ((to.0 - x).abs(), -(to.1 - y).abs());
+    let (step_x, step_y) = ((to.0 - x).signum(), (to.1 - y).signum());
+    let mut error = dx + dy;
+    let mut tiles = vec![];
+    while (x, y) != to {
+        let double = 2 * error;
+        if double >= dy {
+            error += dy;
+            x += step_x;
+        }
+        if double <= dx {
+            error += dx;
+            y += step_y;
+        }
+        tiles.push((x, y));
+    }
+    tiles
+}
@@ -0,0 +1,196 @@
+use crate::game::Game;
+use crate::object::damage::{DamageKind, MAX_RESISTANCE};
+use crate::object::fighter::{DeathCallback, Fighter};
+use crate::object::loot::drop_loot;
+use crate::object::store::{Entity, Objects};
+use crate::object::Object;
+
+use tcod::colors::*;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StatusKind {
+    /// loses `potency` hit points every turn
+    Poison,
+    /// heals `potency` hit points every turn
+    Regeneration,
+    /// loses its turns
+    Stun,
+    /// twice as fast
+    Haste,
+    /// half as fast
+    Slow,
+    /// stumbles around in random directions
+    Confusion,
+    /// can't see further than the next tile
+    Blindness,
+}
+
+/// A temporary effect on a fighter, counting down every turn.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Status {
+    pub kind: StatusKind,
+    /// turns left
+    pub turns: i32,
+    /// how strong the effect is, only poison and regeneration have one
+    #[serde(default)]
+    pub potency: i32,
+}
+
+impl std::fmt::Display for StatusKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        use StatusKind::*;
+        match self {
+            Poison => write!(f, "Poisoned"),
+            Regeneration => write!(f, "Regen"),
+            Stun => write!(f, "Stunned"),
+            Haste => write!(f, "Hasted"),
+            Slow => write!(f, "Slowed"),
+            Confusion => write!(f, "Confused"),
+            Blindness => write!(f, "Blind"),
+        }
+    }
+}
+
+impl StatusKind {
+    /// what's said about a fighter when it gets and when it loses the status
+    fn messages(self) -> (&'static str, &'static str) {
+        use StatusKind::*;
+        match self {
+            Poison => ("is poisoned!", "is no longer poisoned."),
+            Regeneration => ("starts to regenerate.", "stops regenerating."),
+            Stun => ("is stunned!", "is no longer stunned."),
+            Haste => ("speeds up!", "slows back down."),
+            Slow => ("slows down!", "speeds back up."),
+            Confusion => ("is confused!", "is no longer confused!"),
+            Blindness => ("is blinded!", "can see again."),
+        }
+    }
+
+    pub fn color(self) -> Color {
+        use StatusKind::*;
+        match self {
+            Poison => LIME,
+            Regeneration => LIGHT_VIOLET,
+            Stun | Confusion | Blindness => LIGHT_BLUE,
+            Haste => LIGHT_CYAN,
+            Slow => ORANGE,
+        }
+    }
+
+    /// statuses that cancel each other out
+    fn opposite(self) -> Option<StatusKind> {
+        use StatusKind::*;
+        match self {
+            Haste => Some(Slow),
+            Slow => Some(Haste),
+            _ => None,
+        }
+    }
+}
+
+impl Fighter {
+    pub fn status(&self, kind: StatusKind) -> Option<&Status> {
+        self.statuses.iter().find(|s| s.kind == kind)
+    }
+
+    pub fn has_status(&self, kind: StatusKind) -> bool {
+        self.status(kind).is_some()
+    }
+}
+
+/// Give `status` to `object`. A status it already has doesn't come twice:
+/// poison adds up, everything else keeps whichever lasts longer and is stronger.
+/// Haste and slowness cancel each other out, and poison can't hurt the immune.
+pub fn apply_status(object: &mut Object, status: Status, game: &mut Game) {
+    let name = object.name.clone();
+    let immune = status.kind == StatusKind::Poison
+        && object.resistance(DamageKind::Poison) >= MAX_RESISTANCE;
+    let fighter = match object.fighter.as_mut() {
+        Some(fighter) if object.alive => fighter,
+        _ => return,
+    };
+
+    if immune {
+        game.messages
+            .add(format!("{} is unaffected by the poison.", name), WHITE);
+        return;
+    }
+
+    if let Some(opposite) = status.kind.opposite() {
+        if fighter.has_status(opposite) {
+            fighter.statuses.retain(|s| s.kind != opposite);
+            game.messages
+                .add(format!("{} {}", name, opposite.messages().1), WHITE);
+            return;
+        }
+    }
+
+    match fighter.statuses.iter_mut().find(|s| s.kind == status.kind) {
+        Some(current) if status.kind == StatusKind::Poison => {
+            current.turns = current.turns.max(status.turns);
+            current.potency += status.potency;
+        }
+        Some(current) => {
+            current.turns = current.turns.max(status.turns);
+            current.potency = current.potency.max(status.potency);
+        }
+        None => fighter.statuses.push(status),
+    }
+    game.messages.add(
+        format!("{} {}", name, status.kind.messages().0),
+        status.kind.color(),
+    );
+}
+
+/// A turn passes for `id`: poison hurts, regeneration heals and statuses run out.
+pub fn tick_statuses(id: Entity, game: &mut Game, objects: &mut Objects) {
+    let statuses = match objects[id].fighter.as_ref() {
+        Some(fighter) if objects[id].alive => fighter.statuses.clone(),
+        _ => return,
+    };
+
+    for status in &statuses {
+        // nothing heals the dead, whatever comes after the poison
+        if !objects[id].alive {
+            break;
+        }
+        match status.kind {
+            StatusKind::Poison => {
+                let object = &mut objects[id];
+                let is_player = object
+                    .fighter
+                    .as_ref()
+                    .is_some_and(|f| f.on_death == DeathCallback::Player);
+                match object.take_damage(status.potency, DamageKind::Poison, game) {
+                    Some(_) if is_player => game.killed_by = Some("poison".into()),
+                    // whoever poisoned it, the player gets the experience
+                    Some(xp) => {
+                        objects[game.player].fighter.as_mut().unwrap().xp += xp;
+                        drop_loot(id, game, objects);
+                    }
+                    None => {}
+                }
+            }
+            StatusKind::Regeneration => objects[id].heal(status.potency),
+            _ => {}
+        }
+    }
+
+    let object = &mut objects[id];
+    let name = object.name.clone();
+    let fighter = match object.fighter.as_mut() {
+        // the poison may have killed it
+        Some(fighter) if object.alive => fighter,
+        _ => return,
+    };
+    for status in fighter.statuses.iter_mut() {
+        status.turns -= 1;
+        if status.turns <= 0 {
+            game.messages
+                .add(format!("{} {}", name, status.kind.messages().1), WHITE);
+        }
+    }
+    fighter.statuses.retain(|s| s.turns > 0);
+}
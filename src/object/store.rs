@@ -0,0 +1,229 @@
+use crate::object::Object;
+
+use std::ops::{Index, IndexMut};
+
+use serde::{Deserialize, Serialize};
+
+/// Stable handle to an object in an `Objects` store. Inserting or removing other
+/// objects never changes what it points to, and once its object is removed the
+/// handle goes stale instead of silently pointing at whatever reuses the slot.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Entity {
+    index: u32,
+    generation: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Slot {
+    generation: u32,
+    object: Option<Object>,
+}
+
+/// All objects of a level, addressed by `Entity` handles.
+/// Freed slots are reused, with a bumped generation so old handles stay invalid.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Objects {
+    slots: Vec<Slot>,
+    free: Vec<u32>,
+}
+
+impl Objects {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn insert(&mut self, object: Object) -> Entity {
+        if let Some(index) = self.free.pop() {
+            let slot = &mut self.slots[index as usize];
+            slot.object = Some(object);
+            Entity {
+                index,
+                generation: slot.generation,
+            }
+        } else {
+            self.slots.push(Slot {
+                generation: 0,
+                object: Some(object),
+            });
+            Entity {
+                index: self.slots.len() as u32 - 1,
+                generation: 0,
+            }
+        }
+    }
+
+    pub fn remove(&mut self, id: Entity) -> Option<Object> {
+        let slot = self.slot_mut(id)?;
+        let object = slot.object.take();
+        slot.generation += 1;
+        self.free.push(id.index);
+        object
+    }
+
+    pub fn contains(&self, id: Entity) -> bool {
+        self.get(id).is_some()
+    }
+
+    pub fn get(&self, id: Entity) -> Option<&Object> {
+        self.slots
+            .get(id.index as usize)
+            .filter(|slot| slot.generation == id.generation)
+            .and_then(|slot| slot.object.as_ref())
+    }
+
+    pub fn get_mut(&mut self, id: Entity) -> Option<&mut Object> {
+        self.slot_mut(id).and_then(|slot| slot.object.as_mut())
+    }
+
+    /// Mutably borrow two *separate* objects
+    pub fn get_two_mut(&mut self, first: Entity, second: Entity) -> (&mut Object, &mut Object) {
+        assert!(
+            first.index != second.index,
+            "Can't borrow the same object twice"
+        );
+        assert!(
+            self.contains(first) && self.contains(second),
+            "Stale entity"
+        );
+
+        let (low, high) = if first.index < second.index {
+            (first, second)
+        } else {
+            (second, first)
+        };
+        let (low_slots, high_slots) = self.slots.split_at_mut(high.index as usize);
+        let low_object = low_slots[low.index as usize].object.as_mut().unwrap();
+        let high_object = high_slots[0].object.as_mut().unwrap();
+
+        if first.index < second.index {
+            (low_object, high_object)
+        } else {
+            (high_object, low_object)
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, &Object)> {
+        self.slots.iter().enumerate().filter_map(|(index, slot)| {
+            slot.object.as_ref().map(|object| {
+                let id = Entity {
+                    index: index as u32,
+                    generation: slot.generation,
+                };
+                (id, object)
+            })
+        })
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Entity, &mut Object)> {
+        self.slots
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(index, slot)| {
+                let generation = slot.generation;
+                slot.object.as_mut().map(|object| {
+                    let id = Entity {
+                        index: index as u32,
+                        generation,
+                    };
+                    (id, object)
+                })
+            })
+    }
+
+    /// handles of every object right now, safe to hold on to while the store changes
+    pub fn ids(&self) -> Vec<Entity> {
+        self.iter().map(|(id, _)| id).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.len() - self.free.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn slot_mut(&mut self, id: Entity) -> Option<&mut Slot> {
+        self.slots
+            .get_mut(id.index as usize)
+            .filter(|slot| slot.generation == id.generation && slot.object.is_some())
+    }
+}
+
+impl Index<Entity> for Objects {
+    type Output = Object;
+
+    fn index(&self, id: Entity) -> &Object {
+        self.get(id).expect("Stale entity")
+    }
+}
+
+impl IndexMut<Entity> for Objects {
+    fn index_mut(&mut self, id: Entity) -> &mut Object {
+        self.get_mut(id).expect("Stale entity")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tcod::colors::WHITE;
+
+    fn object(name: &str) -> Object {
+        Object::new(0, 0, '?', WHITE, name, false)
+    }
+
+    #[test]
+    fn handles_go_stale_once_removed() {
+        let mut objects = Objects::new();
+        let id = objects.insert(object("orc"));
+        let other = objects.insert(object("troll"));
+
+        assert_eq!(objects.remove(id).map(|o| o.name), Some("orc".into()));
+        assert!(!objects.contains(id));
+        assert!(objects.get(id).is_none());
+        assert!(objects.remove(id).is_none());
+        assert_eq!(objects[other].name, "troll");
+        assert_eq!(objects.len(), 1);
+    }
+
+    #[test]
+    fn reused_slots_reject_old_handles() {
+        let mut objects = Objects::new();
+        let old = objects.insert(object("orc"));
+        objects.remove(old);
+        let new = objects.insert(object("troll"));
+
+        assert_eq!(new.index, old.index);
+        assert_ne!(new, old);
+        assert!(objects.get(old).is_none());
+        assert!(objects.get_mut(old).is_none());
+        assert!(objects.remove(old).is_none());
+        assert_eq!(objects[new].name, "troll");
+    }
+
+    #[test]
+    fn get_two_mut_works_in_either_order() {
+        let mut objects = Objects::new();
+        let first = objects.insert(object("orc"));
+        let second = objects.insert(object("troll"));
+
+        let (a, b) = objects.get_two_mut(first, second);
+        assert_eq!((a.name.as_str(), b.name.as_str()), ("orc", "troll"));
+        let (a, b) = objects.get_two_mut(second, first);
+        assert_eq!((a.name.as_str(), b.name.as_str()), ("troll", "orc"));
+        a.name = "ogre".into();
+        assert_eq!(objects[second].name, "ogre");
+    }
+
+    #[test]
+    #[should_panic(expected = "Stale entity")]
+    fn get_two_mut_rejects_stale_handles() {
+        let mut objects = Objects::new();
+        let first = objects.insert(object("orc"));
+        let second = objects.insert(object("troll"));
+        objects.remove(second);
+        objects.get_two_mut(first, second);
+    }
+}